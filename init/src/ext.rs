@@ -411,6 +411,36 @@ impl<T: ?Sized + crate::PinCtor<A>, A> crate::PinCtor<NewUnsafeCell<A>> for Unsa
     }
 }
 
+impl<T: ?Sized + crate::TryCtor<A>, A> crate::TryCtor<NewUnsafeCell<A>> for UnsafeCell<T> {
+    type Error = T::Error;
+
+    fn try_init(
+        uninit: crate::Uninit<'_, Self>,
+        NewUnsafeCell(args): NewUnsafeCell<A>,
+    ) -> Result<crate::Init<'_, Self>, Self::Error> {
+        // SAFETY: UnsafeCell has the same layout as `T`
+        let value = unsafe { crate::Uninit::from_raw(uninit.as_ptr() as *mut T) };
+        value.try_init(args)?.take_ownership();
+        // SAFETY: ^^^ The value was initialized
+        Ok(unsafe { uninit.assume_init() })
+    }
+}
+
+impl<T: ?Sized + crate::TryPinCtor<A>, A> crate::TryPinCtor<NewUnsafeCell<A>> for UnsafeCell<T> {
+    type Error = T::Error;
+
+    fn try_pin_init(
+        uninit: crate::Uninit<'_, Self>,
+        NewUnsafeCell(args): NewUnsafeCell<A>,
+    ) -> Result<crate::PinInit<'_, Self>, Self::Error> {
+        // SAFETY: UnsafeCell has the same layout as `T` and it will remain in the pinned type-state
+        let value = unsafe { crate::Uninit::from_raw(uninit.as_ptr() as *mut T) };
+        value.try_pin_init(args)?.take_ownership();
+        // SAFETY: ^^^ The value was initialized
+        Ok(unsafe { uninit.assume_init().pin() })
+    }
+}
+
 /// The layout provider for [`UnsafeCell`]
 pub struct UnsafeCellLayoutProvider;
 
@@ -5,7 +5,7 @@
     unsafe_op_in_unsafe_fn,
     clippy::undocumented_unsafe_blocks
 )]
-#![feature(dropck_eyepatch, ptr_metadata)]
+#![feature(dropck_eyepatch, ptr_metadata, unsize)]
 
 //! ## init
 //!
@@ -33,6 +33,8 @@ pub mod pin_slice_writer;
 mod ptr;
 pub mod slice_writer;
 pub mod source;
+pub mod str;
+pub mod unsize;
 
 #[cfg(feature = "alloc")]
 pub mod boxed;
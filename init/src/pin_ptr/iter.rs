@@ -38,6 +38,16 @@ impl<'a, T> IterPinInit<'a, T> {
         // SAFETY: the caller guarantees that this iterator isn't exhausted
         unsafe { self.raw.next_unchecked() }.pin()
     }
+
+    /// The next_back element of the iterator without checking if it's exhausted
+    ///
+    /// # Safety
+    ///
+    /// The iterator must not be exhausted
+    pub unsafe fn next_back_unchecked(&mut self) -> PinInit<'a, T> {
+        // SAFETY: the caller guarantees that this iterator isn't exhausted
+        unsafe { self.raw.next_back_unchecked() }.pin()
+    }
 }
 
 impl<'a, T> Iterator for IterPinInit<'a, T> {
@@ -48,6 +58,12 @@ impl<'a, T> Iterator for IterPinInit<'a, T> {
     }
 }
 
+impl<'a, T> DoubleEndedIterator for IterPinInit<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.raw.next_back().map(Init::pin)
+    }
+}
+
 impl<'a, T> IntoIterator for PinInit<'a, [T]> {
     type Item = PinInit<'a, T>;
     type IntoIter = IterPinInit<'a, T>;
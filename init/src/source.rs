@@ -20,7 +20,7 @@ unsafe impl<T: ?Sized> LayoutProvider<T, T> for SourceLayoutProvider {
 
     unsafe fn cast(ptr: core::ptr::NonNull<u8>, args: &T) -> core::ptr::NonNull<T> {
         let meta = core::ptr::metadata::<T>(args);
-        NonNull::from_raw_parts(ptr.cast(), meta)
+        NonNull::from_raw_parts(ptr.cast::<()>(), meta)
     }
 }
 
@@ -32,7 +32,7 @@ unsafe impl<T: ?Sized> LayoutProvider<T, &T> for SourceLayoutProvider {
 
     unsafe fn cast(ptr: core::ptr::NonNull<u8>, args: &&T) -> core::ptr::NonNull<T> {
         let meta = core::ptr::metadata::<T>(*args);
-        NonNull::from_raw_parts(ptr.cast(), meta)
+        NonNull::from_raw_parts(ptr.cast::<()>(), meta)
     }
 }
 
@@ -44,7 +44,7 @@ unsafe impl<T: ?Sized> LayoutProvider<T, &mut T> for SourceLayoutProvider {
 
     unsafe fn cast(ptr: core::ptr::NonNull<u8>, args: &&mut T) -> core::ptr::NonNull<T> {
         let meta = core::ptr::metadata::<T>(*args);
-        NonNull::from_raw_parts(ptr.cast(), meta)
+        NonNull::from_raw_parts(ptr.cast::<()>(), meta)
     }
 }
 
@@ -60,7 +60,28 @@ unsafe impl<T: ?Sized> LayoutProvider<T, Init<'_, T>> for SourceLayoutProvider {
 
     unsafe fn cast(ptr: core::ptr::NonNull<u8>, args: &Init<'_, T>) -> core::ptr::NonNull<T> {
         let meta = core::ptr::metadata::<T>(args.get());
-        NonNull::from_raw_parts(ptr.cast(), meta)
+        NonNull::from_raw_parts(ptr.cast::<()>(), meta)
+    }
+}
+
+/// A constructor which clones the value with [`CloneCtor`], and takes the layout from it
+///
+/// Unlike `&T`, this doesn't require `T` to implement `HasLayoutProvider<&T>`
+pub struct Cloned<'a, T: ?Sized>(pub &'a T);
+
+impl<T: ?Sized> HasLayoutProvider<Cloned<'_, T>> for T {
+    type LayoutProvider = SourceLayoutProvider;
+}
+
+// SAFETY: Copying the layout and metadata is always safe
+unsafe impl<T: ?Sized> LayoutProvider<T, Cloned<'_, T>> for SourceLayoutProvider {
+    fn layout_of(args: &Cloned<'_, T>) -> Option<core::alloc::Layout> {
+        Some(Layout::for_value::<T>(args.0))
+    }
+
+    unsafe fn cast(ptr: core::ptr::NonNull<u8>, args: &Cloned<'_, T>) -> core::ptr::NonNull<T> {
+        let meta = core::ptr::metadata::<T>(args.0);
+        NonNull::from_raw_parts(ptr.cast::<()>(), meta)
     }
 }
 
@@ -76,7 +97,7 @@ unsafe impl<T: ?Sized> LayoutProvider<T, Pin<&T>> for SourceLayoutProvider {
 
     unsafe fn cast(ptr: core::ptr::NonNull<u8>, args: &Pin<&T>) -> core::ptr::NonNull<T> {
         let meta = core::ptr::metadata::<T>(&**args);
-        NonNull::from_raw_parts(ptr.cast(), meta)
+        NonNull::from_raw_parts(ptr.cast::<()>(), meta)
     }
 }
 
@@ -92,7 +113,7 @@ unsafe impl<T: ?Sized> LayoutProvider<T, Pin<&mut T>> for SourceLayoutProvider {
 
     unsafe fn cast(ptr: core::ptr::NonNull<u8>, args: &Pin<&mut T>) -> core::ptr::NonNull<T> {
         let meta = core::ptr::metadata::<T>(&**args);
-        NonNull::from_raw_parts(ptr.cast(), meta)
+        NonNull::from_raw_parts(ptr.cast::<()>(), meta)
     }
 }
 
@@ -108,7 +129,7 @@ unsafe impl<T: ?Sized> LayoutProvider<T, PinInit<'_, T>> for SourceLayoutProvide
 
     unsafe fn cast(ptr: core::ptr::NonNull<u8>, args: &PinInit<'_, T>) -> core::ptr::NonNull<T> {
         let meta = core::ptr::metadata::<T>(args.get());
-        NonNull::from_raw_parts(ptr.cast(), meta)
+        NonNull::from_raw_parts(ptr.cast::<()>(), meta)
     }
 }
 
@@ -133,6 +154,13 @@ impl<T: ?Sized + CloneCtor> CtorArgs<T> for &T {
     }
 }
 
+impl<T: ?Sized + CloneCtor> CtorArgs<T> for Cloned<'_, T> {
+    #[inline]
+    fn init_into(self, uninit: Uninit<'_, T>) -> Init<'_, T> {
+        CloneCtor::clone_ctor(uninit, self.0)
+    }
+}
+
 impl<T: ?Sized + PinMoveCtor> PinCtorArgs<T> for PinInit<'_, T> {
     #[inline]
     fn pin_init_into(self, uninit: Uninit<'_, T>) -> PinInit<'_, T> {
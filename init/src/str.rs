@@ -0,0 +1,231 @@
+//! Constructors and layout providers for string slices
+
+use core::{alloc::Layout, fmt, pin::Pin, ptr::NonNull};
+
+use crate::{
+    config_value::{CloneTag, ConfigValue, MoveTag, PinCloneTag, PinMoveTag, PinTakeTag, TakeTag},
+    ctor::{CloneCtor, MoveCtor, TakeCtor},
+    layout_provider::{HasLayoutProvider, LayoutProvider},
+    pin_ctor::{PinCloneCtor, PinMoveCtor, PinTakeCtor},
+    source::SourceLayoutProvider,
+    Ctor, Init, PinInit, Uninit,
+};
+
+impl HasLayoutProvider<&str> for str {
+    type LayoutProvider = SourceLayoutProvider;
+}
+
+/// Copy the bytes of `s` into `uninit`
+///
+/// # Panics
+///
+/// Panics if the length of `s` doesn't equal the length of `uninit`
+fn copy_str<'a>(uninit: Uninit<'a, str>, s: &str) -> Init<'a, str> {
+    // SAFETY: `str` has the same layout as `[u8]`
+    let bytes = unsafe { Uninit::from_raw(uninit.as_ptr() as *mut [u8]) };
+    bytes.copy_from_slice(s.as_bytes()).take_ownership();
+    // SAFETY: the bytes were copied from a `str`, so they are valid UTF-8
+    unsafe { uninit.assume_init() }
+}
+
+impl MoveCtor for str {
+    const IS_MOVE_TRIVIAL: ConfigValue<Self, MoveTag> = {
+        // SAFETY: `str` is just bytes, so it is trivially movable
+        unsafe { ConfigValue::yes() }
+    };
+
+    #[inline]
+    fn move_ctor<'this>(uninit: Uninit<'this, Self>, p: Init<Self>) -> Init<'this, Self> {
+        copy_str(uninit, p.get())
+    }
+}
+
+impl TakeCtor for str {
+    const IS_TAKE_TRIVIAL: ConfigValue<Self, TakeTag> = {
+        // SAFETY: `str` is just bytes, so it is trivially takable
+        unsafe { ConfigValue::yes() }
+    };
+
+    #[inline]
+    fn take_ctor<'this>(uninit: Uninit<'this, Self>, p: &mut Self) -> Init<'this, Self> {
+        copy_str(uninit, p)
+    }
+}
+
+impl CloneCtor for str {
+    const IS_CLONE_TRIVIAL: ConfigValue<Self, CloneTag> = {
+        // SAFETY: `str` is just bytes, so it is trivially clone-able
+        unsafe { ConfigValue::yes() }
+    };
+
+    #[inline]
+    fn clone_ctor<'this>(uninit: Uninit<'this, Self>, p: &Self) -> Init<'this, Self> {
+        copy_str(uninit, p)
+    }
+}
+
+impl PinMoveCtor for str {
+    const IS_MOVE_TRIVIAL: ConfigValue<Self, PinMoveTag> = {
+        // SAFETY: `str` is just bytes, so it is trivially movable
+        unsafe { ConfigValue::yes() }
+    };
+
+    #[inline]
+    fn pin_move_ctor<'this>(uninit: Uninit<'this, Self>, p: PinInit<Self>) -> PinInit<'this, Self> {
+        copy_str(uninit, p.get()).pin()
+    }
+}
+
+impl PinTakeCtor for str {
+    const IS_TAKE_TRIVIAL: ConfigValue<Self, PinTakeTag> = {
+        // SAFETY: `str` is just bytes, so it is trivially takable
+        unsafe { ConfigValue::yes() }
+    };
+
+    #[inline]
+    fn pin_take_ctor<'this>(
+        uninit: Uninit<'this, Self>,
+        p: Pin<&mut Self>,
+    ) -> PinInit<'this, Self> {
+        copy_str(uninit, &p).pin()
+    }
+}
+
+impl PinCloneCtor for str {
+    const IS_CLONE_TRIVIAL: ConfigValue<Self, PinCloneTag> = {
+        // SAFETY: `str` is just bytes, so it is trivially clone-able
+        unsafe { ConfigValue::yes() }
+    };
+
+    #[inline]
+    fn pin_clone_ctor<'this>(uninit: Uninit<'this, Self>, p: Pin<&Self>) -> PinInit<'this, Self> {
+        copy_str(uninit, &p).pin()
+    }
+}
+
+/// A string constructor which writes the formatted arguments in place
+///
+/// The arguments are formatted once in [`Formatted::new`] to calculate the length,
+/// and once more to write the string, so the `Display` impls used must be deterministic
+#[derive(Clone, Copy)]
+pub struct Formatted<'a> {
+    args: fmt::Arguments<'a>,
+    len: usize,
+}
+
+impl<'a> Formatted<'a> {
+    /// Calculate the length of the formatted arguments
+    ///
+    /// # Panics
+    ///
+    /// If any of the formatting trait implementations return an error
+    pub fn new(args: fmt::Arguments<'a>) -> Self {
+        struct Counter(usize);
+
+        impl fmt::Write for Counter {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                self.0 += s.len();
+                Ok(())
+            }
+        }
+
+        let mut counter = Counter(0);
+        fmt::write(&mut counter, args)
+            .expect("a formatting trait implementation returned an error");
+
+        Self {
+            args,
+            len: counter.0,
+        }
+    }
+
+    /// The length of the formatted string in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the formatted string is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// The layout provider for [`Formatted`]
+pub struct FormattedLayoutProvider;
+
+impl HasLayoutProvider<Formatted<'_>> for str {
+    type LayoutProvider = FormattedLayoutProvider;
+}
+
+// SAFETY: the layout is the same as a `[u8]` of the formatted length, which `str` shares
+unsafe impl LayoutProvider<str, Formatted<'_>> for FormattedLayoutProvider {
+    fn layout_of(args: &Formatted<'_>) -> Option<Layout> {
+        Layout::array::<u8>(args.len).ok()
+    }
+
+    unsafe fn cast(ptr: NonNull<u8>, args: &Formatted<'_>) -> NonNull<str> {
+        NonNull::from_raw_parts(ptr.cast::<()>(), args.len)
+    }
+}
+
+impl Ctor<Formatted<'_>> for str {
+    fn init<'u>(uninit: Uninit<'u, Self>, args: Formatted<'_>) -> Init<'u, Self> {
+        struct Writer {
+            ptr: *mut u8,
+            remaining: usize,
+        }
+
+        impl fmt::Write for Writer {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                if s.len() > self.remaining {
+                    return Err(fmt::Error);
+                }
+
+                // SAFETY: `ptr` is valid for writes of `remaining` bytes, checked above
+                unsafe {
+                    self.ptr.copy_from_nonoverlapping(s.as_ptr(), s.len());
+                    self.ptr = self.ptr.add(s.len());
+                }
+
+                self.remaining -= s.len();
+                Ok(())
+            }
+        }
+
+        #[cold]
+        #[inline(never)]
+        fn length_changed() -> ! {
+            panic!("the formatted length changed between calculating the layout and initializing the string")
+        }
+
+        let len = core::ptr::metadata(uninit.as_ptr());
+        assert_eq!(
+            len, args.len,
+            "the string length doesn't match the formatted length"
+        );
+
+        let mut writer = Writer {
+            ptr: uninit.as_ptr() as *mut u8,
+            remaining: len,
+        };
+
+        if fmt::write(&mut writer, args.args).is_err() || writer.remaining != 0 {
+            length_changed()
+        }
+
+        // SAFETY: all `len` bytes were written by `fmt::write`, which only writes whole `str`s
+        // so the bytes are valid UTF-8
+        unsafe { uninit.assume_init() }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_boxed_str() {
+    let bx = crate::boxed::boxed::<str, _>("hello world");
+    assert_eq!(&*bx, "hello world");
+
+    let bx =
+        crate::boxed::boxed::<str, _>(Formatted::new(format_args!("{} + {} = {}", 1, 2, 1 + 2)));
+    assert_eq!(&*bx, "1 + 2 = 3");
+}
@@ -0,0 +1,171 @@
+//! Constructors for unsized types, which initialize a concrete type and coerce it
+//!
+//! This allows constructing `dyn Trait` (or `[T]` from `[T; N]`) in place
+
+use core::{
+    alloc::Layout,
+    marker::{PhantomData, Unsize},
+    ptr::NonNull,
+};
+
+use crate::{
+    layout_provider::{HasLayoutProvider, LayoutProvider},
+    Ctor, CtorArgs, Init, PinCtor, PinCtorArgs, PinInit, TryCtor, TryCtorArgs, TryPinCtor,
+    TryPinCtorArgs, Uninit,
+};
+
+/// A constructor which initializes a `Concrete` value with `Args`, then
+/// coerces it to an unsized type like `dyn Trait`
+pub struct Coerce<Concrete, Args> {
+    args: Args,
+    ty: PhantomData<fn() -> Concrete>,
+}
+
+impl<Concrete, Args> Coerce<Concrete, Args> {
+    /// Create a new coercing constructor
+    pub const fn new(args: Args) -> Self {
+        Self {
+            args,
+            ty: PhantomData,
+        }
+    }
+}
+
+impl<Concrete, Args: Clone> Clone for Coerce<Concrete, Args> {
+    fn clone(&self) -> Self {
+        Self::new(self.args.clone())
+    }
+}
+
+impl<Concrete, Args: Copy> Copy for Coerce<Concrete, Args> {}
+
+/// The layout provider for [`Coerce`]
+pub struct CoerceLayoutProvider;
+
+impl<T, Concrete, Args> HasLayoutProvider<Coerce<Concrete, Args>> for T
+where
+    T: ?Sized,
+    Concrete: Unsize<T> + HasLayoutProvider<Args>,
+{
+    type LayoutProvider = CoerceLayoutProvider;
+}
+
+// SAFETY: the layout and pointer are the same as `Concrete`'s, the metadata
+// comes from the unsizing coercion
+unsafe impl<T, Concrete, Args> LayoutProvider<T, Coerce<Concrete, Args>> for CoerceLayoutProvider
+where
+    T: ?Sized,
+    Concrete: Unsize<T> + HasLayoutProvider<Args>,
+{
+    #[inline]
+    fn layout_of(args: &Coerce<Concrete, Args>) -> Option<Layout> {
+        crate::layout_provider::layout_of::<Concrete, Args>(&args.args)
+    }
+
+    #[inline]
+    unsafe fn cast(ptr: NonNull<u8>, args: &Coerce<Concrete, Args>) -> NonNull<T> {
+        // SAFETY: guaranteed by caller
+        let ptr: NonNull<Concrete> =
+            unsafe { crate::layout_provider::cast::<Concrete, Args>(ptr, &args.args) };
+        ptr
+    }
+
+    #[inline]
+    fn is_zeroed(args: &Coerce<Concrete, Args>) -> bool {
+        crate::layout_provider::is_zeroed::<Concrete, Args>(&args.args)
+    }
+}
+
+/// Get an `Uninit` for the concrete value behind `uninit`
+fn concrete<'a, T: ?Sized, Concrete: Unsize<T>>(uninit: &Uninit<'a, T>) -> Uninit<'a, Concrete> {
+    // SAFETY: `uninit` was created from `CoerceLayoutProvider`, so it points to
+    // enough space for a `Concrete`, which is initialized before `uninit` is used
+    unsafe { Uninit::from_raw(uninit.as_ptr().cast::<Concrete>().cast_mut()) }
+}
+
+impl<T, Concrete, Args> CtorArgs<T> for Coerce<Concrete, Args>
+where
+    T: ?Sized,
+    Concrete: Unsize<T> + Ctor<Args>,
+{
+    #[inline]
+    fn init_into(self, uninit: Uninit<'_, T>) -> Init<'_, T> {
+        concrete::<T, Concrete>(&uninit)
+            .init(self.args)
+            .take_ownership();
+        // SAFETY: the concrete value was initialized
+        unsafe { uninit.assume_init() }
+    }
+
+    #[inline]
+    #[doc(hidden)]
+    fn __is_clone_cheap() -> bool {
+        <Concrete as Ctor<Args>>::__is_args_clone_cheap()
+    }
+}
+
+impl<T, Concrete, Args> TryCtorArgs<T> for Coerce<Concrete, Args>
+where
+    T: ?Sized,
+    Concrete: Unsize<T> + TryCtor<Args>,
+{
+    type Error = Concrete::Error;
+
+    #[inline]
+    fn try_init_into(self, uninit: Uninit<'_, T>) -> Result<Init<'_, T>, Self::Error> {
+        concrete::<T, Concrete>(&uninit)
+            .try_init(self.args)?
+            .take_ownership();
+        // SAFETY: the concrete value was initialized
+        Ok(unsafe { uninit.assume_init() })
+    }
+}
+
+impl<T, Concrete, Args> PinCtorArgs<T> for Coerce<Concrete, Args>
+where
+    T: ?Sized,
+    Concrete: Unsize<T> + PinCtor<Args>,
+{
+    #[inline]
+    fn pin_init_into(self, uninit: Uninit<'_, T>) -> PinInit<'_, T> {
+        concrete::<T, Concrete>(&uninit)
+            .pin_init(self.args)
+            .take_ownership();
+        // SAFETY: the concrete value was initialized, and it will remain in the pinned type-state
+        unsafe { uninit.assume_init().pin() }
+    }
+}
+
+impl<T, Concrete, Args> TryPinCtorArgs<T> for Coerce<Concrete, Args>
+where
+    T: ?Sized,
+    Concrete: Unsize<T> + TryPinCtor<Args>,
+{
+    type Error = Concrete::Error;
+
+    #[inline]
+    fn try_pin_init_into(self, uninit: Uninit<'_, T>) -> Result<PinInit<'_, T>, Self::Error> {
+        concrete::<T, Concrete>(&uninit)
+            .try_pin_init(self.args)?
+            .take_ownership();
+        // SAFETY: the concrete value was initialized, and it will remain in the pinned type-state
+        Ok(unsafe { uninit.assume_init().pin() })
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_coerce() {
+    use core::fmt::Display;
+
+    let bx = crate::boxed::boxed::<dyn Display, _>(Coerce::<u32, _>::new(10u32));
+    assert_eq!(alloc::format!("{bx}"), "10");
+
+    let bx = crate::pin_boxed::pin_boxed::<dyn Display, _>(Coerce::<bool, _>::new(true));
+    assert_eq!(alloc::format!("{bx}"), "true");
+
+    let bx = crate::boxed::boxed::<[u8], _>(Coerce::<[u8; 3], _>::new(
+        crate::slice::ctor::CopyArgs(7u8),
+    ));
+    assert_eq!(*bx, [7, 7, 7]);
+}
@@ -0,0 +1,197 @@
+//! Attributes which configure how a [`PThreadMutex`](crate::PThreadMutex) behaves
+
+use std::{io, marker::PhantomPinned};
+
+use init::{PinInit, TryPinCtor};
+use libc::{
+    c_int, pthread_mutexattr_destroy, pthread_mutexattr_init, pthread_mutexattr_setprotocol,
+    pthread_mutexattr_setpshared, pthread_mutexattr_setrobust, pthread_mutexattr_settype,
+    pthread_mutexattr_t,
+};
+
+use crate::error::check;
+
+extern "C" {
+    // not exposed by the libc crate for glibc targets
+    fn pthread_mutexattr_setprioceiling(
+        attr: *mut pthread_mutexattr_t,
+        prioceiling: c_int,
+    ) -> c_int;
+}
+
+/// How a mutex behaves when it's locked again by the thread which holds it,
+/// or unlocked by a thread which doesn't hold it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MutexKind {
+    /// The platform's default behavior, which is the same as [`Normal`](Self::Normal) on glibc
+    #[default]
+    Default,
+    /// Relocking deadlocks, and unlocking a mutex which isn't held is undefined behavior
+    Normal,
+    /// The owning thread may lock the mutex again, and it must be unlocked the same number of times
+    Recursive,
+    /// Relocking and unlocking a mutex which isn't held are reported as errors
+    ErrorCheck,
+}
+
+/// How holding a mutex affects the scheduling priority of the owning thread
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MutexProtocol {
+    /// The owning thread's priority isn't affected
+    #[default]
+    None,
+    /// The owning thread runs at the highest priority of the threads blocked on the mutex
+    Inherit,
+    /// The owning thread runs at least at the given priority ceiling
+    Protect(i32),
+}
+
+/// A builder for the attributes of a [`PThreadMutex`](crate::PThreadMutex)
+///
+/// ```
+/// # use mutex::{MutexAttr, MutexKind};
+/// let attr = MutexAttr::new().kind(MutexKind::ErrorCheck).robust(true);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MutexAttr {
+    kind: MutexKind,
+    protocol: MutexProtocol,
+    robust: bool,
+    process_shared: bool,
+}
+
+impl MutexAttr {
+    /// The default attributes
+    pub const fn new() -> Self {
+        Self {
+            kind: MutexKind::Default,
+            protocol: MutexProtocol::None,
+            robust: false,
+            process_shared: false,
+        }
+    }
+
+    /// Set how the mutex behaves when it's relocked or unlocked incorrectly
+    pub const fn kind(mut self, kind: MutexKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Set how the mutex affects the priority of the owning thread
+    pub const fn protocol(mut self, protocol: MutexProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Set whether the mutex is robust
+    ///
+    /// If the owner of a robust mutex exits while holding it, the next thread to lock it
+    /// is told that the owner died (see [`PThreadMutex::lock_robust`](crate::PThreadMutex::lock_robust)),
+    /// instead of blocking forever
+    pub const fn robust(mut self, robust: bool) -> Self {
+        self.robust = robust;
+        self
+    }
+
+    /// Set whether the mutex may be used by other processes
+    ///
+    /// The mutex must be placed in memory which is shared with those processes,
+    /// see [`SharedMutex`](crate::SharedMutex)
+    pub const fn process_shared(mut self, process_shared: bool) -> Self {
+        self.process_shared = process_shared;
+        self
+    }
+
+    /// Get how the mutex behaves when it's relocked or unlocked incorrectly
+    pub const fn get_kind(&self) -> MutexKind {
+        self.kind
+    }
+
+    /// Get how the mutex affects the priority of the owning thread
+    pub const fn get_protocol(&self) -> MutexProtocol {
+        self.protocol
+    }
+
+    /// Get whether the mutex is robust
+    pub const fn is_robust(&self) -> bool {
+        self.robust
+    }
+
+    /// Get whether the mutex may be used by other processes
+    pub const fn is_process_shared(&self) -> bool {
+        self.process_shared
+    }
+}
+
+#[repr(transparent)]
+pub(crate) struct PThreadMutexAttr {
+    pub(crate) value: pthread_mutexattr_t,
+    _unpin: PhantomPinned,
+}
+
+impl Drop for PThreadMutexAttr {
+    fn drop(&mut self) {
+        // SAFETY: the attribute object was initialized in `pin_init`
+        unsafe { pthread_mutexattr_destroy(&mut self.value) };
+    }
+}
+
+impl TryPinCtor<MutexAttr> for PThreadMutexAttr {
+    type Error = io::Error;
+
+    fn try_pin_init(
+        mut uninit: init::Uninit<'_, Self>,
+        attr: MutexAttr,
+    ) -> io::Result<PinInit<'_, Self>> {
+        let ptr = uninit.as_mut_ptr().cast();
+        // SAFETY: `ptr` is valid for writes
+        check(unsafe { pthread_mutexattr_init(ptr) })?;
+        // SAFETY: the attribute object was initialized, and will be destroyed if configuring it fails
+        let mut attr_obj = unsafe { uninit.assume_init().pin() };
+        // SAFETY: the attribute object isn't moved out of
+        let ptr = unsafe { &mut attr_obj.get_mut_unchecked().value as *mut pthread_mutexattr_t };
+
+        let kind = match attr.kind {
+            MutexKind::Default => libc::PTHREAD_MUTEX_DEFAULT,
+            MutexKind::Normal => libc::PTHREAD_MUTEX_NORMAL,
+            MutexKind::Recursive => libc::PTHREAD_MUTEX_RECURSIVE,
+            MutexKind::ErrorCheck => libc::PTHREAD_MUTEX_ERRORCHECK,
+        };
+        // SAFETY: the attribute object is initialized
+        check(unsafe { pthread_mutexattr_settype(ptr, kind) })?;
+
+        let (protocol, ceiling) = match attr.protocol {
+            MutexProtocol::None => (libc::PTHREAD_PRIO_NONE, None),
+            MutexProtocol::Inherit => (libc::PTHREAD_PRIO_INHERIT, None),
+            MutexProtocol::Protect(ceiling) => (libc::PTHREAD_PRIO_PROTECT, Some(ceiling)),
+        };
+        // SAFETY: the attribute object is initialized
+        check(unsafe { pthread_mutexattr_setprotocol(ptr, protocol) })?;
+        if let Some(ceiling) = ceiling {
+            // SAFETY: the attribute object is initialized
+            check(unsafe { pthread_mutexattr_setprioceiling(ptr, ceiling) })?;
+        }
+
+        let robust = if attr.robust {
+            libc::PTHREAD_MUTEX_ROBUST
+        } else {
+            libc::PTHREAD_MUTEX_STALLED
+        };
+        // SAFETY: the attribute object is initialized
+        check(unsafe { pthread_mutexattr_setrobust(ptr, robust) })?;
+
+        let pshared = if attr.process_shared {
+            libc::PTHREAD_PROCESS_SHARED
+        } else {
+            libc::PTHREAD_PROCESS_PRIVATE
+        };
+        // SAFETY: the attribute object is initialized
+        check(unsafe { pthread_mutexattr_setpshared(ptr, pshared) })?;
+
+        Ok(attr_obj)
+    }
+}
+
+impl init::layout_provider::HasLayoutProvider<MutexAttr> for PThreadMutexAttr {
+    type LayoutProvider = init::layout_provider::SizedLayoutProvider;
+}
@@ -0,0 +1,274 @@
+//! A pinned condition variable built on `pthread_cond_t`
+
+use std::{
+    cell::UnsafeCell,
+    marker::PhantomPinned,
+    pin::Pin,
+    ptr::null_mut,
+    sync::atomic::{AtomicPtr, Ordering},
+    time::Duration,
+};
+
+use init::{PinCtor, PinInit};
+use libc::{
+    pthread_cond_broadcast, pthread_cond_destroy, pthread_cond_init, pthread_cond_signal,
+    pthread_cond_t, pthread_cond_timedwait, pthread_cond_wait, pthread_condattr_destroy,
+    pthread_condattr_init, pthread_condattr_setclock, pthread_condattr_t, pthread_mutex_t,
+    CLOCK_MONOTONIC, EOWNERDEAD, ETIMEDOUT,
+};
+
+use crate::{MutexGuard, OwnerDead};
+
+#[repr(transparent)]
+struct PThreadCondAttr {
+    value: pthread_condattr_t,
+    _unpin: PhantomPinned,
+}
+
+impl Drop for PThreadCondAttr {
+    fn drop(&mut self) {
+        // SAFETY: the attribute object was initialized in `pin_init`
+        unsafe { pthread_condattr_destroy(&mut self.value) };
+    }
+}
+
+impl PinCtor for PThreadCondAttr {
+    fn pin_init(mut uninit: init::Uninit<'_, Self>, (): ()) -> PinInit<'_, Self> {
+        let ptr = uninit.as_mut_ptr().cast();
+        // SAFETY: `ptr` is valid for writes
+        let err = unsafe { pthread_condattr_init(ptr) };
+        assert_eq!(err, 0);
+        // SAFETY: the attribute object was just initialized
+        let err = unsafe { pthread_condattr_setclock(ptr, CLOCK_MONOTONIC) };
+        assert_eq!(err, 0);
+        // SAFETY: the attribute object was initialized above
+        unsafe { uninit.assume_init().pin() }
+    }
+}
+
+impl init::layout_provider::HasLayoutProvider for PThreadCondAttr {
+    type LayoutProvider = init::layout_provider::SizedLayoutProvider;
+}
+
+/// A condition variable which is always used with the same pinned [`Mutex`](crate::Mutex)
+///
+/// Timeouts are measured with `CLOCK_MONOTONIC`, so they aren't affected by changes to the system time
+pub struct Condvar {
+    cond: UnsafeCell<pthread_cond_t>,
+    mutex: AtomicPtr<pthread_mutex_t>,
+    _unpin: PhantomPinned,
+}
+
+// SAFETY: `pthread_cond_t` may be used from any thread
+unsafe impl Send for Condvar {}
+// SAFETY: `pthread_cond_t` may be used from any thread
+unsafe impl Sync for Condvar {}
+
+impl Drop for Condvar {
+    fn drop(&mut self) {
+        // SAFETY: the condition variable was initialized in `pin_init`, and there
+        // can't be any waiters since they borrow `self`
+        unsafe { pthread_cond_destroy(self.cond.get()) };
+    }
+}
+
+impl PinCtor for Condvar {
+    fn pin_init(mut uninit: init::Uninit<'_, Self>, (): ()) -> PinInit<'_, Self> {
+        init::stack_pin_init((), |attr: Pin<&mut PThreadCondAttr>| {
+            let ptr = uninit.as_mut_ptr();
+            // SAFETY: `ptr` is valid for writes
+            unsafe {
+                core::ptr::addr_of_mut!((*ptr).mutex).write(AtomicPtr::new(null_mut()));
+                core::ptr::addr_of_mut!((*ptr)._unpin).write(PhantomPinned);
+            }
+            // SAFETY: `ptr` is valid for writes, and `attr` is initialized
+            let err = unsafe {
+                pthread_cond_init(core::ptr::addr_of_mut!((*ptr).cond).cast(), &attr.value)
+            };
+            assert_eq!(err, 0);
+            // SAFETY: all fields were initialized above
+            unsafe { uninit.assume_init().pin() }
+        })
+    }
+}
+
+impl init::layout_provider::HasLayoutProvider for Condvar {
+    type LayoutProvider = init::layout_provider::SizedLayoutProvider;
+}
+
+/// Whether a timed wait on a [`Condvar`] timed out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitTimeoutResult(bool);
+
+impl WaitTimeoutResult {
+    /// If the wait timed out
+    pub fn timed_out(&self) -> bool {
+        self.0
+    }
+}
+
+impl Condvar {
+    /// Check that this condition variable is only ever used with one mutex,
+    /// since waiting with different mutexes is undefined behavior
+    fn verify(&self, mutex: *mut pthread_mutex_t) {
+        match self
+            .mutex
+            .compare_exchange(null_mut(), mutex, Ordering::Relaxed, Ordering::Relaxed)
+        {
+            Ok(_) => {}
+            Err(current) => assert!(
+                current == mutex,
+                "attempted to use a condition variable with two mutexes"
+            ),
+        }
+    }
+
+    /// Block until this condition variable is notified
+    ///
+    /// The mutex is unlocked while waiting, and is locked again before returning.
+    /// This may wake up spuriously, so it should be called in a loop (see [`wait_while`](Self::wait_while))
+    ///
+    /// If the mutex is robust and another owner exited while holding it, this returns
+    /// [`OwnerDead`] with the mutex locked again, and the value must be marked consistent with
+    /// [`MutexGuard::make_consistent`] before the guard is dropped
+    ///
+    /// # Panics
+    ///
+    /// If this condition variable was used with a different mutex
+    pub fn wait<T: ?Sized>(
+        self: Pin<&Self>,
+        guard: &mut MutexGuard<'_, T>,
+    ) -> Result<(), OwnerDead> {
+        let mutex = guard.mutex.as_lock().as_raw();
+        self.verify(mutex);
+        // SAFETY: the mutex is locked by `guard`, and both objects are initialized and pinned
+        let err = unsafe { pthread_cond_wait(self.cond.get(), mutex) };
+        if err == EOWNERDEAD {
+            return Err(OwnerDead { guard: () });
+        }
+        assert_eq!(err, 0);
+        Ok(())
+    }
+
+    /// Block until this condition variable is notified and `condition` returns false
+    ///
+    /// Stops waiting if the previous owner of a robust mutex exited, see [`wait`](Self::wait)
+    ///
+    /// # Panics
+    ///
+    /// If this condition variable was used with a different mutex
+    pub fn wait_while<T: ?Sized>(
+        self: Pin<&Self>,
+        guard: &mut MutexGuard<'_, T>,
+        mut condition: impl FnMut(Pin<&mut T>) -> bool,
+    ) -> Result<(), OwnerDead> {
+        while condition(guard.as_mut()) {
+            self.wait(guard)?;
+        }
+        Ok(())
+    }
+
+    /// Block until this condition variable is notified or `dur` has elapsed
+    ///
+    /// This may wake up spuriously, so the condition should be rechecked after it returns.
+    /// Reports if the previous owner of a robust mutex exited, see [`wait`](Self::wait)
+    ///
+    /// # Panics
+    ///
+    /// If this condition variable was used with a different mutex
+    pub fn wait_timeout<T: ?Sized>(
+        self: Pin<&Self>,
+        guard: &mut MutexGuard<'_, T>,
+        dur: Duration,
+    ) -> Result<WaitTimeoutResult, OwnerDead> {
+        let mutex = guard.mutex.as_lock().as_raw();
+        self.verify(mutex);
+        let deadline = crate::time::deadline(CLOCK_MONOTONIC, dur);
+        // SAFETY: the mutex is locked by `guard`, and both objects are initialized and pinned
+        let err = unsafe { pthread_cond_timedwait(self.cond.get(), mutex, &deadline) };
+        if err == EOWNERDEAD {
+            return Err(OwnerDead { guard: () });
+        }
+        assert!(err == 0 || err == ETIMEDOUT);
+        Ok(WaitTimeoutResult(err == ETIMEDOUT))
+    }
+
+    /// Wake up one thread which is blocked on this condition variable
+    pub fn notify_one(self: Pin<&Self>) {
+        // SAFETY: the condition variable is initialized and pinned
+        let err = unsafe { pthread_cond_signal(self.cond.get()) };
+        assert_eq!(err, 0);
+    }
+
+    /// Wake up all threads which are blocked on this condition variable
+    pub fn notify_all(self: Pin<&Self>) {
+        // SAFETY: the condition variable is initialized and pinned
+        let err = unsafe { pthread_cond_broadcast(self.cond.get()) };
+        assert_eq!(err, 0);
+    }
+}
+
+#[test]
+fn condvar() {
+    use crate::Mutex;
+
+    let mutex = init::pin_boxed::pin_boxed::<Mutex<bool>, _>(());
+    let condvar = init::pin_boxed::pin_boxed::<Condvar, _>(());
+    let (mutex, condvar) = (mutex.as_ref(), condvar.as_ref());
+
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            mutex.lock().as_mut().set(true);
+            condvar.notify_one();
+        });
+
+        let mut guard = mutex.lock();
+        condvar.wait_while(&mut guard, |ready| !*ready).unwrap();
+        assert!(*guard);
+    });
+}
+
+#[test]
+fn condvar_timeout() {
+    use crate::Mutex;
+
+    let mutex = init::pin_boxed::pin_boxed::<Mutex<i32>, _>(());
+    let condvar = init::pin_boxed::pin_boxed::<Condvar, _>(());
+
+    let mut guard = mutex.as_ref().lock();
+    let start = std::time::Instant::now();
+    let result = condvar
+        .as_ref()
+        .wait_timeout(&mut guard, Duration::from_millis(10))
+        .unwrap();
+    assert!(result.timed_out());
+    assert!(start.elapsed() >= Duration::from_millis(10));
+}
+
+#[test]
+fn condvar_robust() {
+    use crate::{Mutex, MutexAttr, NewMutexWith};
+
+    let mutex = init::pin_boxed::pin_boxed::<Mutex<i32>, _>(NewMutexWith(
+        MutexAttr::new().robust(true),
+        (),
+    ));
+    let condvar = init::pin_boxed::pin_boxed::<Condvar, _>(());
+    let (mutex, condvar) = (mutex.as_ref(), condvar.as_ref());
+
+    let mut guard = mutex.lock();
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            core::mem::forget(mutex.lock());
+            condvar.notify_one();
+        });
+        // the wait may wake up spuriously before the other thread exits
+        while condvar.wait(&mut guard).is_ok() {}
+    });
+
+    // the mutex is locked again, and can be recovered
+    guard.as_mut().set(1);
+    guard.make_consistent();
+    drop(guard);
+    assert_eq!(*mutex.lock(), 1);
+}
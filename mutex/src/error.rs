@@ -0,0 +1,222 @@
+//! The errors reported by the pthread wrappers
+
+use std::{fmt, io};
+
+use init::{TryPinCtor, TryPinCtorArgs};
+use libc::{c_int, EBUSY, EDEADLK, EINVAL, EOWNERDEAD, ETIMEDOUT};
+
+/// Why locking a mutex failed
+///
+/// `G` is the guard which holds the lock if the previous owner of a robust mutex
+/// exited while holding it
+pub enum LockError<G = ()> {
+    /// The mutex is held by another thread (`EBUSY`)
+    WouldBlock,
+    /// The timeout elapsed before the lock could be acquired (`ETIMEDOUT`)
+    TimedOut,
+    /// The mutex is an [`ErrorCheck`](crate::MutexKind::ErrorCheck) mutex
+    /// which is already held by this thread (`EDEADLK`)
+    Deadlock,
+    /// The mutex or the timeout is invalid (`EINVAL`), for example if the priority of this thread
+    /// is above the ceiling of a [`Protect`](crate::MutexProtocol::Protect) mutex
+    Invalid,
+    /// The mutex is robust, and the previous owner exited while holding it (`EOWNERDEAD`)
+    ///
+    /// The lock is held, just like when [`lock_robust`](crate::PThreadMutex::lock_robust)
+    /// reports this, so the state it protects must be marked consistent before it's unlocked
+    OwnerDead(OwnerDead<G>),
+    /// Locking failed for another reason
+    Os(io::Error),
+}
+
+impl LockError {
+    /// Convert the return code of a pthread function
+    ///
+    /// `EOWNERDEAD` means that the lock is held, so the caller has to keep track of it
+    pub(crate) fn check(code: c_int) -> Result<(), Self> {
+        match code {
+            0 => Ok(()),
+            EBUSY => Err(Self::WouldBlock),
+            ETIMEDOUT => Err(Self::TimedOut),
+            EDEADLK => Err(Self::Deadlock),
+            EINVAL => Err(Self::Invalid),
+            EOWNERDEAD => Err(Self::OwnerDead(OwnerDead { guard: () })),
+            _ => Err(Self::Os(io::Error::from_raw_os_error(code))),
+        }
+    }
+
+    /// Attach the guard which holds the lock if the previous owner exited
+    pub(crate) fn with_guard<G>(self, guard: impl FnOnce() -> G) -> LockError<G> {
+        match self {
+            Self::WouldBlock => LockError::WouldBlock,
+            Self::TimedOut => LockError::TimedOut,
+            Self::Deadlock => LockError::Deadlock,
+            Self::Invalid => LockError::Invalid,
+            Self::OwnerDead(OwnerDead { guard: () }) => {
+                LockError::OwnerDead(OwnerDead { guard: guard() })
+            }
+            Self::Os(err) => LockError::Os(err),
+        }
+    }
+}
+
+impl<G> LockError<G> {
+    /// If the mutex is held by another thread
+    pub fn is_would_block(&self) -> bool {
+        matches!(self, Self::WouldBlock)
+    }
+
+    /// If the timeout elapsed before the lock could be acquired
+    pub fn is_timed_out(&self) -> bool {
+        matches!(self, Self::TimedOut)
+    }
+
+    /// If the previous owner of a robust mutex exited while holding it, so the lock is held
+    pub fn is_owner_dead(&self) -> bool {
+        matches!(self, Self::OwnerDead(_))
+    }
+
+    /// Get the OS error code
+    pub fn raw_os_error(&self) -> c_int {
+        match self {
+            Self::WouldBlock => EBUSY,
+            Self::TimedOut => ETIMEDOUT,
+            Self::Deadlock => EDEADLK,
+            Self::Invalid => EINVAL,
+            Self::OwnerDead(_) => EOWNERDEAD,
+            Self::Os(err) => err.raw_os_error().unwrap_or(0),
+        }
+    }
+}
+
+impl<G> fmt::Debug for LockError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WouldBlock => f.write_str("WouldBlock"),
+            Self::TimedOut => f.write_str("TimedOut"),
+            Self::Deadlock => f.write_str("Deadlock"),
+            Self::Invalid => f.write_str("Invalid"),
+            Self::OwnerDead(err) => f.debug_tuple("OwnerDead").field(err).finish(),
+            Self::Os(err) => f.debug_tuple("Os").field(err).finish(),
+        }
+    }
+}
+
+impl<G> fmt::Display for LockError<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WouldBlock => f.write_str("the mutex is held by another thread"),
+            Self::TimedOut => f.write_str("timed out waiting for the mutex"),
+            Self::Deadlock => f.write_str("the mutex is already held by this thread"),
+            Self::Invalid => f.write_str("the mutex or the timeout is invalid"),
+            Self::OwnerDead(err) => fmt::Display::fmt(err, f),
+            Self::Os(err) => write!(f, "failed to lock the mutex: {err}"),
+        }
+    }
+}
+
+impl<G> std::error::Error for LockError<G> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Os(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl<G> From<LockError<G>> for io::Error {
+    fn from(err: LockError<G>) -> Self {
+        match err {
+            LockError::Os(err) => err,
+            err => io::Error::from_raw_os_error(err.raw_os_error()),
+        }
+    }
+}
+
+/// The previous owner of a robust mutex exited while holding it
+///
+/// The lock is held, but the state it protects may be inconsistent. If the mutex is unlocked
+/// without being marked consistent again, it becomes permanently unusable
+pub struct OwnerDead<G = ()> {
+    pub(crate) guard: G,
+}
+
+impl<G> OwnerDead<G> {
+    /// Get the guard which holds the lock
+    pub fn into_inner(self) -> G {
+        self.guard
+    }
+
+    /// Get a reference to the guard which holds the lock
+    pub fn get_ref(&self) -> &G {
+        &self.guard
+    }
+
+    /// Get a mutable reference to the guard which holds the lock
+    pub fn get_mut(&mut self) -> &mut G {
+        &mut self.guard
+    }
+}
+
+impl<G> fmt::Debug for OwnerDead<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OwnerDead").finish_non_exhaustive()
+    }
+}
+
+impl<G> fmt::Display for OwnerDead<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the previous owner of the mutex exited while holding it")
+    }
+}
+
+impl<G> std::error::Error for OwnerDead<G> {}
+
+/// Why constructing a [`Mutex`](crate::Mutex) failed
+#[derive(Debug)]
+pub enum NewMutexError<E> {
+    /// Initializing the `pthread_mutex_t` failed
+    Lock(io::Error),
+    /// Initializing the value failed
+    Value(E),
+}
+
+impl<E: fmt::Display> fmt::Display for NewMutexError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lock(err) => write!(f, "failed to initialize the mutex: {err}"),
+            Self::Value(err) => write!(f, "failed to initialize the value: {err}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for NewMutexError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Lock(err) => Some(err),
+            Self::Value(err) => Some(err),
+        }
+    }
+}
+
+/// Map the error of a fallible constructor
+pub(crate) struct MapErr<A, F>(pub A, pub F);
+
+impl<T: ?Sized + TryPinCtor<A>, A, F: FnOnce(T::Error) -> E, E> TryPinCtorArgs<T> for MapErr<A, F> {
+    type Error = E;
+
+    fn try_pin_init_into(
+        self,
+        uninit: init::Uninit<'_, T>,
+    ) -> Result<init::PinInit<'_, T>, Self::Error> {
+        T::try_pin_init(uninit, self.0).map_err(self.1)
+    }
+}
+
+/// Convert the return code of a pthread function which reports errors directly
+pub(crate) fn check(code: c_int) -> io::Result<()> {
+    match code {
+        0 => Ok(()),
+        _ => Err(io::Error::from_raw_os_error(code)),
+    }
+}
@@ -0,0 +1,235 @@
+//! A small mutex built directly on Linux futexes
+
+use std::{
+    marker::PhantomPinned,
+    pin::Pin,
+    ptr::null,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use init::{PinCtor, PinInit};
+
+use crate::raw::RawLock;
+
+const UNLOCKED: u32 = 0;
+const LOCKED: u32 = 1;
+/// Locked, and there may be threads blocked in `futex_wait`
+const CONTENDED: u32 = 2;
+
+/// A mutex which is a single `u32`, using `futex(2)` to block and wake up threads
+///
+/// Unlike [`PThreadMutex`](crate::PThreadMutex) it has no attributes, so it's never
+/// recursive, error-checking or robust
+pub struct FutexMutex {
+    state: AtomicU32,
+    _unpin: PhantomPinned,
+}
+
+impl Default for FutexMutex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PinCtor for FutexMutex {
+    fn pin_init(uninit: init::Uninit<'_, Self>, (): ()) -> PinInit<'_, Self> {
+        uninit.write(Self::new()).pin()
+    }
+}
+
+impl init::layout_provider::HasLayoutProvider for FutexMutex {
+    type LayoutProvider = init::layout_provider::SizedLayoutProvider;
+}
+
+fn futex_wait(state: &AtomicU32, expected: u32) {
+    // SAFETY: `state` is a valid futex word, and a null timeout blocks until woken
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            state.as_ptr(),
+            libc::FUTEX_WAIT | libc::FUTEX_PRIVATE_FLAG,
+            expected,
+            null::<libc::timespec>(),
+        )
+    };
+}
+
+fn futex_wake_one(state: &AtomicU32) {
+    // SAFETY: `state` is a valid futex word
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            state.as_ptr(),
+            libc::FUTEX_WAKE | libc::FUTEX_PRIVATE_FLAG,
+            1,
+        )
+    };
+}
+
+impl FutexMutex {
+    /// Create a new unlocked mutex
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU32::new(UNLOCKED),
+            _unpin: PhantomPinned,
+        }
+    }
+
+    /// Acquire the lock, blocking until it's available
+    pub fn lock(self: Pin<&Self>) {
+        if self
+            .state
+            .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            self.lock_contended();
+        }
+    }
+
+    #[cold]
+    fn lock_contended(&self) {
+        let mut spins = 100;
+        let mut state = self.state.load(Ordering::Relaxed);
+
+        // spin for a bit while the lock is held without waiters, in case it's released quickly
+        while state == LOCKED && spins != 0 {
+            core::hint::spin_loop();
+            spins -= 1;
+            state = self.state.load(Ordering::Relaxed);
+        }
+
+        if state == UNLOCKED {
+            match self.state.compare_exchange(
+                UNLOCKED,
+                LOCKED,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return,
+                Err(current) => state = current,
+            }
+        }
+
+        loop {
+            // mark the lock as contended, since this thread may block, so that `unlock` wakes it up
+            if state != CONTENDED && self.state.swap(CONTENDED, Ordering::Acquire) == UNLOCKED {
+                return;
+            }
+
+            futex_wait(&self.state, CONTENDED);
+            state = self.state.swap(CONTENDED, Ordering::Acquire);
+            if state == UNLOCKED {
+                return;
+            }
+        }
+    }
+
+    /// Try to acquire the lock without blocking
+    pub fn try_lock(self: Pin<&Self>) -> bool {
+        self.state
+            .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    /// Release the lock
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be currently locked
+    pub unsafe fn force_unlock(self: Pin<&Self>) {
+        if self.state.swap(UNLOCKED, Ordering::Release) == CONTENDED {
+            futex_wake_one(&self.state);
+        }
+    }
+}
+
+// SAFETY: the state can only go from `UNLOCKED` to locked once until it's unlocked again
+unsafe impl RawLock for FutexMutex {
+    fn lock(self: Pin<&Self>) {
+        FutexMutex::lock(self)
+    }
+
+    fn try_lock(self: Pin<&Self>) -> bool {
+        FutexMutex::try_lock(self)
+    }
+
+    unsafe fn unlock(self: Pin<&Self>) {
+        // SAFETY: guaranteed by caller
+        unsafe { FutexMutex::force_unlock(self) }
+    }
+}
+
+// SAFETY: the lock provides mutual exclusion. Unlike a `PThreadMutex`, it can be moved while
+// it's unlocked, since the kernel only refers to its address while a thread is blocked on it,
+// and that thread borrows the mutex
+#[cfg(feature = "lock_api")]
+unsafe impl lock_api::RawMutex for FutexMutex {
+    const INIT: Self = Self::new();
+
+    type GuardMarker = lock_api::GuardSend;
+
+    fn lock(&self) {
+        // SAFETY: the lock doesn't rely on its address while it's unlocked, see above
+        FutexMutex::lock(unsafe { Pin::new_unchecked(self) })
+    }
+
+    fn try_lock(&self) -> bool {
+        // SAFETY: the lock doesn't rely on its address while it's unlocked, see above
+        FutexMutex::try_lock(unsafe { Pin::new_unchecked(self) })
+    }
+
+    unsafe fn unlock(&self) {
+        // SAFETY: the lock is held, as guaranteed by the caller
+        unsafe { FutexMutex::force_unlock(Pin::new_unchecked(self)) }
+    }
+
+    fn is_locked(&self) -> bool {
+        self.state.load(Ordering::Relaxed) != UNLOCKED
+    }
+}
+
+#[test]
+fn futex_mutex() {
+    use crate::Mutex;
+
+    assert_eq!(core::mem::size_of::<FutexMutex>(), 4);
+
+    let mutex = init::pin_boxed::pin_boxed::<Mutex<usize, FutexMutex>, _>(());
+    let mutex = mutex.as_ref();
+
+    std::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..1000 {
+                    let mut guard = mutex.lock();
+                    let value = *guard;
+                    guard.as_mut().set(value + 1);
+                }
+            });
+        }
+    });
+
+    assert_eq!(*mutex.lock(), 4000);
+    let guard = mutex.try_lock().unwrap();
+    assert!(mutex.try_lock().is_none());
+    drop(guard);
+}
+
+#[test]
+#[cfg(feature = "lock_api")]
+fn lock_api() {
+    use crate::{LockApi, Mutex};
+
+    let mutex = lock_api::Mutex::<FutexMutex, i32>::new(0);
+    *mutex.lock() += 1;
+    assert!(!mutex.is_locked());
+    assert_eq!(mutex.into_inner(), 1);
+
+    let mutex = init::pin_boxed::pin_boxed::<Mutex<i32, LockApi<FutexMutex>>, _>(());
+    let mutex = mutex.as_ref();
+    let guard = mutex.lock();
+    assert!(mutex.try_lock().is_none());
+    drop(guard);
+    *mutex.try_lock().unwrap().as_mut() += 1;
+    assert_eq!(*mutex.lock(), 1);
+}
@@ -1,33 +1,66 @@
 #![feature(intrinsics, core_intrinsics)]
 
+mod attr;
+mod condvar;
+mod error;
+#[cfg(target_os = "linux")]
+mod futex;
+mod poison;
+mod raw;
+mod reentrant;
+mod rwlock;
+#[cfg(target_os = "linux")]
+mod shared;
+mod time;
+
+pub use attr::{MutexAttr, MutexKind, MutexProtocol};
+pub use condvar::{Condvar, WaitTimeoutResult};
+pub use error::{LockError, NewMutexError, OwnerDead};
+#[cfg(target_os = "linux")]
+pub use futex::FutexMutex;
+#[cfg(feature = "lock_api")]
+pub use raw::LockApi;
+pub use raw::RawLock;
+pub use reentrant::{ReentrantMutex, ReentrantMutexGuard, ReentrantMutexLayoutProvider};
+pub use rwlock::{
+    NewRwLock, NewRwLockWith, PThreadRwLock, RwLock, RwLockKind, RwLockLayoutProvider,
+    RwLockReadGuard, RwLockWriteGuard,
+};
+#[cfg(target_os = "linux")]
+pub use shared::SharedMutex;
+
 use std::{
     alloc::Layout,
     cell::{Cell, UnsafeCell},
+    convert::Infallible,
+    io,
     marker::{PhantomData, PhantomPinned},
     ops::Deref,
     pin::Pin,
+    sync::{LockResult, PoisonError, TryLockError, TryLockResult},
+    time::{Duration, Instant},
 };
 
 use init::{
     layout_provider::{HasLayoutProvider, LayoutProvider},
-    PinCtor, PinInit,
+    PinCtor, PinInit, TryPinCtor,
 };
 use libc::{
-    pthread_mutex_destroy, pthread_mutex_init, pthread_mutex_lock, pthread_mutex_t,
-    pthread_mutex_trylock, pthread_mutex_unlock, pthread_mutexattr_destroy, pthread_mutexattr_init,
-    pthread_mutexattr_t,
+    pthread_mutex_consistent, pthread_mutex_destroy, pthread_mutex_init, pthread_mutex_lock,
+    pthread_mutex_t, pthread_mutex_trylock, pthread_mutex_unlock, EOWNERDEAD,
 };
 
-#[repr(transparent)]
-struct PThreadMutexAttr {
-    value: pthread_mutexattr_t,
-    _unpin: PhantomPinned,
-}
-
-impl Drop for PThreadMutexAttr {
-    fn drop(&mut self) {
-        unsafe { pthread_mutexattr_destroy(&mut self.value) };
-    }
+use attr::PThreadMutexAttr;
+use error::{check, MapErr};
+
+#[cfg(target_env = "gnu")]
+extern "C" {
+    // available since glibc 2.30, but not exposed by the libc crate
+    fn pthread_mutex_clocklock(
+        mutex: *mut pthread_mutex_t,
+        clock: libc::clockid_t,
+        abstime: *const libc::timespec,
+    ) -> libc::c_int;
 }
 
 #[repr(transparent)]
@@ -45,25 +78,40 @@ impl Drop for PThreadMutex {
     }
 }
 
-impl PinCtor for PThreadMutexAttr {
-    fn pin_init(mut uninit: init::Uninit<'_, Self>, (): ()) -> PinInit<'_, Self> {
-        let ptr = uninit.as_mut_ptr().cast();
-        let err = unsafe { pthread_mutexattr_init(ptr) };
-        assert_eq!(err, 0);
-        unsafe { uninit.assume_init().pin() }
+impl PinCtor for PThreadMutex {
+    fn pin_init(uninit: init::Uninit<'_, Self>, (): ()) -> PinInit<'_, Self> {
+        Self::pin_init(uninit, MutexAttr::new())
     }
 }
 
-impl init::layout_provider::HasLayoutProvider for PThreadMutexAttr {
-    type LayoutProvider = init::layout_provider::SizedLayoutProvider;
+impl PinCtor<MutexAttr> for PThreadMutex {
+    fn pin_init(uninit: init::Uninit<'_, Self>, attr: MutexAttr) -> PinInit<'_, Self> {
+        match Self::try_pin_init(uninit, attr) {
+            Ok(init) => init,
+            Err(err) => panic!("failed to initialize the mutex: {err}"),
+        }
+    }
 }
 
-impl PinCtor for PThreadMutex {
-    fn pin_init(mut uninit: init::Uninit<'_, Self>, (): ()) -> PinInit<'_, Self> {
-        init::stack_pin_init((), |attr: Pin<&mut PThreadMutexAttr>| {
-            unsafe { pthread_mutex_init(uninit.as_mut_ptr().cast(), &attr.value) };
-            unsafe { uninit.assume_init().pin() }
-        })
+impl TryPinCtor for PThreadMutex {
+    type Error = io::Error;
+
+    fn try_pin_init(uninit: init::Uninit<'_, Self>, (): ()) -> io::Result<PinInit<'_, Self>> {
+        Self::try_pin_init(uninit, MutexAttr::new())
+    }
+}
+
+impl TryPinCtor<MutexAttr> for PThreadMutex {
+    type Error = io::Error;
+
+    fn try_pin_init(
+        mut uninit: init::Uninit<'_, Self>,
+        attr: MutexAttr,
+    ) -> io::Result<PinInit<'_, Self>> {
+        init::try_stack_pin_init(attr, |attr: Pin<&mut PThreadMutexAttr>| {
+            check(unsafe { pthread_mutex_init(uninit.as_mut_ptr().cast(), &attr.value) })?;
+            Ok(unsafe { uninit.assume_init().pin() })
+        })?
     }
 }
 
@@ -71,6 +119,10 @@ impl init::layout_provider::HasLayoutProvider for PThreadMutex {
     type LayoutProvider = init::layout_provider::SizedLayoutProvider;
 }
 
+impl init::layout_provider::HasLayoutProvider<MutexAttr> for PThreadMutex {
+    type LayoutProvider = init::layout_provider::SizedLayoutProvider;
+}
+
 impl PThreadMutex {
     pub const fn new() -> Self {
         Self {
@@ -79,79 +131,329 @@ impl PThreadMutex {
         }
     }
 
+    /// Get a pointer to the underlying `pthread_mutex_t`
+    pub fn as_raw(self: Pin<&Self>) -> *mut pthread_mutex_t {
+        self.lock.get()
+    }
+
+    /// # Panics
+    ///
+    /// If locking fails (see [`lock_checked`](Self::lock_checked)), or if the mutex is robust and
+    /// the previous owner exited while holding it. In that case the mutex is unlocked without
+    /// being marked consistent, so it becomes unusable instead of blocking other threads forever.
+    /// Use [`lock_robust`](Self::lock_robust) to recover from that
     pub fn lock(self: Pin<&Self>) {
+        match self.lock_checked() {
+            Ok(()) => (),
+            Err(LockError::OwnerDead(_)) => self.owner_dead(),
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// Lock the mutex, and report why it failed instead of panicking
+    ///
+    /// If the mutex is robust and the previous owner exited while holding it, this returns
+    /// [`LockError::OwnerDead`] while holding the lock, see [`lock_robust`](Self::lock_robust)
+    pub fn lock_checked(self: Pin<&Self>) -> Result<(), LockError> {
+        let x = &self.lock;
+        LockError::check(unsafe { pthread_mutex_lock(x.get()) })
+    }
+
+    /// Lock the mutex, and report if the previous owner of a robust mutex exited while holding it
+    ///
+    /// The mutex is locked in both cases, but on error it must be marked consistent with
+    /// [`make_consistent`](Self::make_consistent) before it's unlocked to be usable again
+    pub fn lock_robust(self: Pin<&Self>) -> Result<(), OwnerDead> {
         let x = &self.lock;
         let val = unsafe { pthread_mutex_lock(x.get()) };
-        assert_eq!(val, 0);
+        if val == EOWNERDEAD {
+            return Err(OwnerDead { guard: () });
+        }
+        if let Err(err) = LockError::check(val) {
+            panic!("{err}")
+        }
+        Ok(())
     }
 
+    /// # Panics
+    ///
+    /// If the mutex is robust and the previous owner exited while holding it,
+    /// see [`lock`](Self::lock)
     pub fn try_lock(self: Pin<&Self>) -> bool {
+        match self.try_lock_checked() {
+            Ok(()) => true,
+            Err(LockError::OwnerDead(_)) => self.owner_dead(),
+            Err(_) => false,
+        }
+    }
+
+    /// Try to lock the mutex without blocking, and report why it failed
+    ///
+    /// [`LockError::WouldBlock`] means that the mutex is held by another thread
+    ///
+    /// If the mutex is robust and the previous owner exited while holding it, this returns
+    /// [`LockError::OwnerDead`] while holding the lock, see [`lock_robust`](Self::lock_robust)
+    pub fn try_lock_checked(self: Pin<&Self>) -> Result<(), LockError> {
+        let x = &self.lock;
+        LockError::check(unsafe { pthread_mutex_trylock(x.get()) })
+    }
+
+    /// Try to lock the mutex, blocking for at most `dur`
+    ///
+    /// The timeout is measured with `CLOCK_MONOTONIC` where `pthread_mutex_clocklock` is available,
+    /// and with `CLOCK_REALTIME` otherwise
+    ///
+    /// If the mutex is robust and the previous owner exited while holding it, this returns
+    /// [`LockError::OwnerDead`] while holding the lock, see [`lock_robust`](Self::lock_robust)
+    pub fn try_lock_for(self: Pin<&Self>, dur: Duration) -> Result<(), LockError> {
         let x = &self.lock;
-        0 == unsafe { pthread_mutex_trylock(x.get()) }
+
+        #[cfg(target_env = "gnu")]
+        let val = {
+            let deadline = time::deadline(libc::CLOCK_MONOTONIC, dur);
+            unsafe { pthread_mutex_clocklock(x.get(), libc::CLOCK_MONOTONIC, &deadline) }
+        };
+        #[cfg(not(target_env = "gnu"))]
+        let val = {
+            let deadline = time::deadline(libc::CLOCK_REALTIME, dur);
+            unsafe { libc::pthread_mutex_timedlock(x.get(), &deadline) }
+        };
+
+        LockError::check(val)
+    }
+
+    /// Release a robust mutex whose previous owner exited while holding it, and panic
+    ///
+    /// The mutex isn't marked consistent, so it becomes unusable, and other threads
+    /// get an error instead of blocking on it forever
+    #[cold]
+    fn owner_dead(self: Pin<&Self>) -> ! {
+        // SAFETY: `EOWNERDEAD` means that this thread acquired the lock
+        unsafe { self.force_unlock() }
+        panic!("{}", OwnerDead { guard: () })
+    }
+
+    /// Try to lock the mutex, blocking until `deadline` at the latest
+    ///
+    /// See [`try_lock_for`](Self::try_lock_for) for details
+    pub fn try_lock_until(self: Pin<&Self>, deadline: Instant) -> Result<(), LockError> {
+        self.try_lock_for(deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Mark the state protected by a robust mutex as consistent after [`lock_robust`](Self::lock_robust)
+    /// or [`LockError::OwnerDead`] reported that the previous owner exited
+    ///
+    /// # Panics
+    ///
+    /// If the mutex isn't robust, isn't inconsistent, or isn't held by this thread
+    pub fn make_consistent(self: Pin<&Self>) {
+        let val = unsafe { pthread_mutex_consistent(self.lock.get()) };
+        assert_eq!(
+            val, 0,
+            "the mutex isn't an inconsistent robust mutex held by this thread"
+        );
     }
 
     /// # Safety
     ///
     /// The mutex must be currently locked
     pub unsafe fn force_unlock(self: Pin<&Self>) {
+        if let Err(err) = unsafe { self.force_unlock_checked() } {
+            panic!("failed to unlock the mutex: {err}")
+        }
+    }
+
+    /// Unlock the mutex, and report why it failed instead of panicking
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be currently locked
+    pub unsafe fn force_unlock_checked(self: Pin<&Self>) -> io::Result<()> {
         let x = &self.lock;
-        let val = unsafe { pthread_mutex_unlock(x.get()) };
-        assert_eq!(val, 0);
+        check(unsafe { pthread_mutex_unlock(x.get()) })
     }
 }
 
 #[repr(C)]
-pub struct Mutex<T: ?Sized> {
-    lock: PThreadMutex,
+pub struct Mutex<T: ?Sized, L = PThreadMutex> {
+    lock: L,
+    poison: poison::Flag,
     value: UnsafeCell<T>,
 }
 
-unsafe impl<T: Send> Send for Mutex<T> {}
-unsafe impl<T: Send> Sync for Mutex<T> {}
+unsafe impl<T: ?Sized + Send, L: Send> Send for Mutex<T, L> {}
+unsafe impl<T: ?Sized + Send, L: Sync> Sync for Mutex<T, L> {}
 
-pub struct MutexGuard<'a, T: ?Sized> {
-    mutex: Pin<&'a Mutex<T>>,
+pub struct MutexGuard<'a, T: ?Sized, L: RawLock = PThreadMutex> {
+    mutex: Pin<&'a Mutex<T, L>>,
+    poison: poison::Guard,
     _not_send: PhantomData<&'static Cell<()>>,
 }
 
-unsafe impl<T: Sync> Sync for MutexGuard<'_, T> {}
+unsafe impl<T: ?Sized + Sync, L: RawLock + Sync> Sync for MutexGuard<'_, T, L> {}
 
 impl<T> Mutex<T> {
     pub const fn new(value: T) -> Self {
         Self {
             lock: PThreadMutex::new(),
+            poison: poison::Flag::new(),
             value: UnsafeCell::new(value),
         }
     }
 }
 
-impl<T: ?Sized> Mutex<T> {
-    pub fn as_lock(self: Pin<&Self>) -> Pin<&PThreadMutex> {
+impl<T, L> Mutex<T, L> {
+    /// Create a mutex which is backed by `lock`
+    pub const fn with_lock(lock: L, value: T) -> Self {
+        Self {
+            lock,
+            poison: poison::Flag::new(),
+            value: UnsafeCell::new(value),
+        }
+    }
+}
+
+impl<T: ?Sized, L: RawLock> Mutex<T, L> {
+    pub fn as_lock(self: Pin<&Self>) -> Pin<&L> {
         let this = unsafe { Pin::into_inner_unchecked(self) };
         unsafe { Pin::new_unchecked(&this.lock) }
     }
 
-    pub fn lock(self: Pin<&Self>) -> MutexGuard<'_, T> {
-        self.as_lock().lock();
+    /// # Safety
+    ///
+    /// The mutex must be locked by this thread, and the lock must not be owned by another guard
+    unsafe fn guard(self: Pin<&Self>) -> MutexGuard<'_, T, L> {
         MutexGuard {
             mutex: self,
+            poison: self.poison.guard(),
             _not_send: PhantomData,
         }
     }
 
-    pub fn try_lock(self: Pin<&Self>) -> Option<MutexGuard<'_, T>> {
+    /// Lock the mutex, ignoring whether it's poisoned
+    ///
+    /// Use [`lock_poisoning`](Self::lock_poisoning) to find out if another thread
+    /// panicked while holding the lock
+    pub fn lock(self: Pin<&Self>) -> MutexGuard<'_, T, L> {
+        self.as_lock().lock();
+        unsafe { self.guard() }
+    }
+
+    /// Lock the mutex, and report if another thread panicked while holding it
+    ///
+    /// The lock is held in both cases, and the guard can be recovered from the error
+    pub fn lock_poisoning(self: Pin<&Self>) -> LockResult<MutexGuard<'_, T, L>> {
+        let guard = self.lock();
+        if self.is_poisoned() {
+            Err(PoisonError::new(guard))
+        } else {
+            Ok(guard)
+        }
+    }
+
+    pub fn try_lock(self: Pin<&Self>) -> Option<MutexGuard<'_, T, L>> {
         if self.as_lock().try_lock() {
-            Some(MutexGuard {
-                mutex: self,
-                _not_send: PhantomData,
-            })
+            Some(unsafe { self.guard() })
         } else {
             None
         }
     }
+
+    /// Try to lock the mutex without blocking, and report if another thread
+    /// panicked while holding it
+    pub fn try_lock_poisoning(self: Pin<&Self>) -> TryLockResult<MutexGuard<'_, T, L>> {
+        let guard = self.try_lock().ok_or(TryLockError::WouldBlock)?;
+        if self.is_poisoned() {
+            Err(TryLockError::Poisoned(PoisonError::new(guard)))
+        } else {
+            Ok(guard)
+        }
+    }
+
+    /// If a thread panicked while holding the lock
+    pub fn is_poisoned(self: Pin<&Self>) -> bool {
+        self.poison.get()
+    }
+
+    /// Clear the poisoned state, after the value was checked or restored to a valid state
+    pub fn clear_poison(self: Pin<&Self>) {
+        self.poison.clear()
+    }
 }
 
-impl<T: ?Sized> MutexGuard<'_, T> {
+impl<T: ?Sized> Mutex<T> {
+    /// Lock the mutex, and report if the previous owner of a robust mutex exited while holding it
+    ///
+    /// See [`PThreadMutex::lock_robust`] for details
+    pub fn lock_robust(
+        self: Pin<&Self>,
+    ) -> Result<MutexGuard<'_, T>, OwnerDead<MutexGuard<'_, T>>> {
+        let result = self.as_lock().lock_robust();
+        let guard = unsafe { self.guard() };
+        match result {
+            Ok(()) => Ok(guard),
+            Err(OwnerDead { guard: () }) => Err(OwnerDead { guard }),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `result` must come from locking this mutex, and the lock must not be owned by another guard
+    unsafe fn guard_checked(
+        self: Pin<&Self>,
+        result: Result<(), LockError>,
+    ) -> Result<MutexGuard<'_, T>, LockError<MutexGuard<'_, T>>> {
+        match result {
+            Ok(()) => Ok(unsafe { self.guard() }),
+            Err(err) => Err(err.with_guard(|| unsafe { self.guard() })),
+        }
+    }
+
+    /// Lock the mutex, and report why it failed instead of panicking
+    ///
+    /// See [`PThreadMutex::lock_checked`] for details. If the previous owner of a robust
+    /// mutex exited while holding it, the guard can be recovered from [`LockError::OwnerDead`]
+    pub fn lock_checked(
+        self: Pin<&Self>,
+    ) -> Result<MutexGuard<'_, T>, LockError<MutexGuard<'_, T>>> {
+        let result = self.as_lock().lock_checked();
+        unsafe { self.guard_checked(result) }
+    }
+
+    /// Try to lock the mutex without blocking, and report why it failed
+    ///
+    /// See [`PThreadMutex::try_lock_checked`] for details
+    pub fn try_lock_checked(
+        self: Pin<&Self>,
+    ) -> Result<MutexGuard<'_, T>, LockError<MutexGuard<'_, T>>> {
+        let result = self.as_lock().try_lock_checked();
+        unsafe { self.guard_checked(result) }
+    }
+
+    /// Try to lock the mutex, blocking for at most `dur`
+    ///
+    /// See [`PThreadMutex::try_lock_for`] for details
+    pub fn try_lock_for(
+        self: Pin<&Self>,
+        dur: Duration,
+    ) -> Result<MutexGuard<'_, T>, LockError<MutexGuard<'_, T>>> {
+        let result = self.as_lock().try_lock_for(dur);
+        unsafe { self.guard_checked(result) }
+    }
+
+    /// Try to lock the mutex, blocking until `deadline` at the latest
+    ///
+    /// See [`PThreadMutex::try_lock_for`] for details
+    pub fn try_lock_until(
+        self: Pin<&Self>,
+        deadline: Instant,
+    ) -> Result<MutexGuard<'_, T>, LockError<MutexGuard<'_, T>>> {
+        let result = self.as_lock().try_lock_until(deadline);
+        unsafe { self.guard_checked(result) }
+    }
+}
+
+impl<T: ?Sized, L: RawLock> MutexGuard<'_, T, L> {
     pub fn as_ref(&self) -> Pin<&T> {
         unsafe { Pin::new_unchecked(&*self.mutex.value.get()) }
     }
@@ -161,7 +463,17 @@ impl<T: ?Sized> MutexGuard<'_, T> {
     }
 }
 
-impl<T: ?Sized> Deref for MutexGuard<'_, T> {
+impl<T: ?Sized> MutexGuard<'_, T> {
+    /// Mark the value as consistent after [`Mutex::lock_robust`] or [`LockError::OwnerDead`]
+    /// reported that the previous owner exited
+    ///
+    /// See [`PThreadMutex::make_consistent`] for details
+    pub fn make_consistent(&mut self) {
+        self.mutex.as_lock().make_consistent()
+    }
+}
+
+impl<T: ?Sized, L: RawLock> Deref for MutexGuard<'_, T, L> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
@@ -169,26 +481,36 @@ impl<T: ?Sized> Deref for MutexGuard<'_, T> {
     }
 }
 
-impl<T: ?Sized> Drop for MutexGuard<'_, T> {
+impl<T: ?Sized, L: RawLock> Drop for MutexGuard<'_, T, L> {
     fn drop(&mut self) {
-        unsafe { self.mutex.as_lock().force_unlock() }
+        self.mutex.poison.done(self.poison);
+        unsafe { self.mutex.as_lock().unlock() }
     }
 }
 
 pub struct NewMutex<A>(pub A);
 
-impl<T: ?Sized + PinCtor> PinCtor for Mutex<T> {
+/// Construct a [`Mutex`] with the given attributes, and initialize the value with `A`
+///
+/// # Panics
+///
+/// Construction panics if the attributes make the mutex [`Recursive`](MutexKind::Recursive),
+/// since relocking it would give out aliasing `&mut T`
+pub struct NewMutexWith<A>(pub MutexAttr, pub A);
+
+impl<T: ?Sized + PinCtor, L: RawLock> PinCtor for Mutex<T, L> {
     fn pin_init(uninit: init::Uninit<'_, Self>, (): ()) -> init::PinInit<'_, Self> {
         init::pin_init_struct! {
             uninit => Self {
                 lock: (),
+                poison: (),
                 value: init::ext::NewUnsafeCell(())
             }
         }
     }
 }
 
-impl<T: ?Sized + PinCtor<A>, A> PinCtor<NewMutex<A>> for Mutex<T> {
+impl<T: ?Sized + PinCtor<A>, A, L: RawLock> PinCtor<NewMutex<A>> for Mutex<T, L> {
     fn pin_init(
         uninit: init::Uninit<'_, Self>,
         NewMutex(args): NewMutex<A>,
@@ -196,55 +518,158 @@ impl<T: ?Sized + PinCtor<A>, A> PinCtor<NewMutex<A>> for Mutex<T> {
         init::pin_init_struct! {
             uninit => Self {
                 lock: (),
+                poison: (),
                 value: init::ext::NewUnsafeCell(args)
             }
         }
     }
 }
 
+impl<T: ?Sized + PinCtor<A>, A> PinCtor<NewMutexWith<A>> for Mutex<T> {
+    fn pin_init(
+        uninit: init::Uninit<'_, Self>,
+        NewMutexWith(attr, args): NewMutexWith<A>,
+    ) -> init::PinInit<'_, Self> {
+        assert!(
+            attr.get_kind() != MutexKind::Recursive,
+            "a `Mutex<T>` can't be recursive"
+        );
+
+        init::pin_init_struct! {
+            uninit => Self {
+                lock: attr,
+                poison: (),
+                value: init::ext::NewUnsafeCell(args)
+            }
+        }
+    }
+}
+
+impl<T: ?Sized + PinCtor> TryPinCtor for Mutex<T> {
+    type Error = io::Error;
+
+    fn try_pin_init(uninit: init::Uninit<'_, Self>, (): ()) -> io::Result<PinInit<'_, Self>> {
+        let args = NewMutex(init::try_pin_ctor::of_pin_ctor(()));
+        Self::try_pin_init(uninit, args).map_err(|err| match err {
+            NewMutexError::Lock(err) => err,
+            NewMutexError::Value(inf) => match inf {},
+        })
+    }
+}
+
+impl<T: ?Sized + TryPinCtor<A>, A> TryPinCtor<NewMutex<A>> for Mutex<T> {
+    type Error = NewMutexError<T::Error>;
+
+    fn try_pin_init(
+        uninit: init::Uninit<'_, Self>,
+        NewMutex(args): NewMutex<A>,
+    ) -> Result<PinInit<'_, Self>, Self::Error> {
+        Self::try_pin_init(uninit, NewMutexWith(MutexAttr::new(), args))
+    }
+}
+
+impl<T: ?Sized + TryPinCtor<A>, A> TryPinCtor<NewMutexWith<A>> for Mutex<T> {
+    type Error = NewMutexError<T::Error>;
+
+    fn try_pin_init(
+        uninit: init::Uninit<'_, Self>,
+        NewMutexWith(attr, args): NewMutexWith<A>,
+    ) -> Result<PinInit<'_, Self>, Self::Error> {
+        if attr.get_kind() == MutexKind::Recursive {
+            return Err(NewMutexError::Lock(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a `Mutex<T>` can't be recursive",
+            )));
+        }
+
+        Ok(init::try_pin_init_struct! {
+            uninit => Self {
+                lock: MapErr(attr, NewMutexError::Lock),
+                poison: MapErr(init::try_pin_ctor::of_pin_ctor(()), |inf: Infallible| match inf {}),
+                value: init::ext::NewUnsafeCell(MapErr(args, NewMutexError::Value))
+            }
+        })
+    }
+}
+
 pub struct MutexLayoutProvider;
 
-impl<T: ?Sized + HasLayoutProvider<A>, A> HasLayoutProvider<NewMutex<A>> for Mutex<T> {
+impl<T: ?Sized + HasLayoutProvider<A>, A, L: RawLock> HasLayoutProvider<NewMutex<A>>
+    for Mutex<T, L>
+{
     type LayoutProvider = MutexLayoutProvider;
 }
 
-impl<T: ?Sized + HasLayoutProvider> HasLayoutProvider for Mutex<T> {
+impl<T: ?Sized + HasLayoutProvider<A>, A> HasLayoutProvider<NewMutexWith<A>> for Mutex<T> {
     type LayoutProvider = MutexLayoutProvider;
 }
 
-unsafe impl<T: ?Sized + HasLayoutProvider<A>, A> LayoutProvider<Mutex<T>, NewMutex<A>>
-    for MutexLayoutProvider
-{
-    fn layout_of(args: &NewMutex<A>) -> Option<std::alloc::Layout> {
-        let lock = Layout::new::<PThreadMutex>();
-        let value = init::layout_provider::layout_of::<T, A>(&args.0)?;
-        Some(lock.extend(value).ok()?.0)
+impl<T: ?Sized + HasLayoutProvider, L: RawLock> HasLayoutProvider for Mutex<T, L> {
+    type LayoutProvider = MutexLayoutProvider;
+}
+
+impl MutexLayoutProvider {
+    fn layout_of<T: ?Sized + HasLayoutProvider<A>, L, A>(args: &A) -> Option<Layout> {
+        let lock = Layout::new::<L>();
+        let (header, _) = lock.extend(Layout::new::<poison::Flag>()).ok()?;
+        let value = init::layout_provider::layout_of::<T, A>(args)?;
+        Some(header.extend(value).ok()?.0.pad_to_align())
     }
 
-    unsafe fn cast(ptr: std::ptr::NonNull<u8>, args: &NewMutex<A>) -> std::ptr::NonNull<Mutex<T>> {
+    unsafe fn cast<T: ?Sized + HasLayoutProvider<A>, L, A>(
+        ptr: std::ptr::NonNull<u8>,
+        args: &A,
+    ) -> std::ptr::NonNull<Mutex<T, L>> {
         unsafe {
-            let ptr = init::layout_provider::cast::<T, A>(ptr, &args.0);
-            core::ptr::NonNull::new_unchecked(ptr.as_ptr() as *mut Mutex<T>)
+            let ptr = init::layout_provider::cast::<T, A>(ptr, args);
+            core::ptr::NonNull::new_unchecked(ptr.as_ptr() as *mut Mutex<T, L>)
         }
     }
 }
 
-unsafe impl<T: ?Sized + HasLayoutProvider> LayoutProvider<Mutex<T>> for MutexLayoutProvider {
+unsafe impl<T: ?Sized + HasLayoutProvider<A>, A, L: RawLock>
+    LayoutProvider<Mutex<T, L>, NewMutex<A>> for MutexLayoutProvider
+{
+    fn layout_of(args: &NewMutex<A>) -> Option<std::alloc::Layout> {
+        Self::layout_of::<T, L, A>(&args.0)
+    }
+
+    unsafe fn cast(
+        ptr: std::ptr::NonNull<u8>,
+        args: &NewMutex<A>,
+    ) -> std::ptr::NonNull<Mutex<T, L>> {
+        unsafe { Self::cast::<T, L, A>(ptr, &args.0) }
+    }
+}
+
+unsafe impl<T: ?Sized + HasLayoutProvider<A>, A> LayoutProvider<Mutex<T>, NewMutexWith<A>>
+    for MutexLayoutProvider
+{
+    fn layout_of(args: &NewMutexWith<A>) -> Option<std::alloc::Layout> {
+        Self::layout_of::<T, PThreadMutex, A>(&args.1)
+    }
+
+    unsafe fn cast(
+        ptr: std::ptr::NonNull<u8>,
+        args: &NewMutexWith<A>,
+    ) -> std::ptr::NonNull<Mutex<T>> {
+        unsafe { Self::cast::<T, PThreadMutex, A>(ptr, &args.1) }
+    }
+}
+
+unsafe impl<T: ?Sized + HasLayoutProvider, L: RawLock> LayoutProvider<Mutex<T, L>>
+    for MutexLayoutProvider
+{
     fn layout_of((): &()) -> Option<std::alloc::Layout> {
-        let lock = Layout::new::<PThreadMutex>();
-        let value = init::layout_provider::layout_of::<T, ()>(&())?;
-        Some(lock.extend(value).ok()?.0.pad_to_align())
+        Self::layout_of::<T, L, ()>(&())
     }
 
-    unsafe fn cast(ptr: std::ptr::NonNull<u8>, (): &()) -> std::ptr::NonNull<Mutex<T>> {
-        unsafe {
-            let ptr = init::layout_provider::cast::<T, ()>(ptr, &());
-            core::ptr::NonNull::new_unchecked(ptr.as_ptr() as *mut Mutex<T>)
-        }
+    unsafe fn cast(ptr: std::ptr::NonNull<u8>, (): &()) -> std::ptr::NonNull<Mutex<T, L>> {
+        unsafe { Self::cast::<T, L, ()>(ptr, &()) }
     }
 }
 
-impl<T: ?Sized + core::fmt::Debug> core::fmt::Debug for MutexGuard<'_, T> {
+impl<T: ?Sized + core::fmt::Debug, L: RawLock> core::fmt::Debug for MutexGuard<'_, T, L> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         T::fmt(self, f)
     }
@@ -258,3 +683,192 @@ fn mutex() {
     drop(_lock);
     let _lock = mutex.as_ref().lock();
 }
+
+#[test]
+fn mutex_unsized() {
+    let mutex = init::pin_boxed::pin_boxed::<Mutex<[u8]>, _>(NewMutex(
+        init::slice::pin_ctor::CopyArgsLen(3, 1),
+    ));
+    let mutex = mutex.as_ref();
+
+    std::thread::scope(|s| {
+        s.spawn(|| mutex.lock().as_mut().get_mut()[1] = 2);
+    });
+    assert_eq!(*mutex.lock(), [1, 2, 1]);
+}
+
+#[test]
+fn recursive() {
+    let mutex =
+        init::pin_boxed::pin_boxed::<PThreadMutex, _>(MutexAttr::new().kind(MutexKind::Recursive));
+    let mutex = mutex.as_ref();
+    mutex.lock();
+    assert!(mutex.try_lock());
+    unsafe {
+        mutex.force_unlock();
+        mutex.force_unlock();
+    }
+}
+
+#[test]
+#[should_panic = "a `Mutex<T>` can't be recursive"]
+fn recursive_mutex() {
+    init::pin_boxed::pin_boxed::<Mutex<i32>, _>(NewMutexWith(
+        MutexAttr::new().kind(MutexKind::Recursive),
+        (),
+    ));
+}
+
+#[test]
+fn robust() {
+    let mutex = init::pin_boxed::pin_boxed::<Mutex<i32>, _>(NewMutexWith(
+        MutexAttr::new()
+            .kind(MutexKind::ErrorCheck)
+            .protocol(MutexProtocol::Inherit)
+            .robust(true),
+        (),
+    ));
+    let mutex = mutex.as_ref();
+
+    std::thread::scope(|s| {
+        s.spawn(|| core::mem::forget(mutex.lock()));
+    });
+
+    let mut guard = mutex.lock_robust().unwrap_err().into_inner();
+    guard.as_mut().set(1);
+    guard.make_consistent();
+    drop(guard);
+
+    assert_eq!(*mutex.lock_robust().unwrap(), 1);
+}
+
+#[test]
+fn robust_unchecked() {
+    let mutex = init::pin_boxed::pin_boxed::<Mutex<i32>, _>(NewMutexWith(
+        MutexAttr::new().robust(true),
+        (),
+    ));
+    let mutex = mutex.as_ref();
+
+    // the lock is still held when the previous owner's death is reported as an error
+    std::thread::scope(|s| {
+        s.spawn(|| core::mem::forget(mutex.lock()));
+    });
+    let err = mutex.try_lock_for(Duration::from_millis(10)).unwrap_err();
+    let LockError::OwnerDead(err) = err else {
+        panic!("{err}")
+    };
+    let mut guard = err.into_inner();
+    guard.as_mut().set(1);
+    guard.make_consistent();
+    drop(guard);
+    assert_eq!(*mutex.try_lock().unwrap(), 1);
+
+    // `lock` can't return the guard, so it makes the mutex unusable before panicking,
+    // instead of leaving other threads blocked on it forever
+    std::thread::scope(|s| {
+        s.spawn(|| core::mem::forget(mutex.lock()));
+    });
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| drop(mutex.lock())));
+    assert!(result.is_err());
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            let err = mutex.lock_checked().unwrap_err();
+            assert_eq!(err.raw_os_error(), libc::ENOTRECOVERABLE);
+        });
+    });
+}
+
+#[test]
+fn timed() {
+    let mutex = init::pin_boxed::pin_boxed::<Mutex<i32>, _>(NewMutexWith(
+        MutexAttr::new().kind(MutexKind::ErrorCheck),
+        (),
+    ));
+    let mutex = mutex.as_ref();
+
+    let guard = mutex.try_lock_for(Duration::from_secs(1)).unwrap();
+
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            let start = Instant::now();
+            let err = mutex.try_lock_for(Duration::from_millis(10)).unwrap_err();
+            assert!(err.is_timed_out());
+            assert!(start.elapsed() >= Duration::from_millis(10));
+
+            let err = mutex.try_lock_until(Instant::now()).unwrap_err();
+            assert!(err.is_timed_out());
+        });
+    });
+
+    // relocking an error-checking mutex is reported instead of deadlocking
+    assert!(matches!(
+        mutex.try_lock_for(Duration::from_millis(10)),
+        Err(LockError::Deadlock)
+    ));
+
+    drop(guard);
+    let deadline = Instant::now() + Duration::from_secs(1);
+    drop(mutex.try_lock_until(deadline).unwrap());
+}
+
+#[test]
+fn checked() {
+    let mutex = init::pin_boxed::try_pin_boxed::<Mutex<i32>, _>(NewMutexWith(
+        MutexAttr::new().kind(MutexKind::ErrorCheck),
+        init::try_pin_ctor::of_pin_ctor(()),
+    ))
+    .unwrap_or_else(|err| panic!("{:?}", err.handle_alloc_and_layout()));
+    let mutex = mutex.as_ref();
+
+    let guard = mutex.lock_checked().unwrap();
+    assert!(matches!(mutex.lock_checked(), Err(LockError::Deadlock)));
+    std::thread::scope(|s| {
+        s.spawn(|| assert!(mutex.try_lock_checked().unwrap_err().is_would_block()));
+    });
+    drop(guard);
+
+    // an invalid priority ceiling is reported instead of being ignored
+    let err = init::pin_boxed::try_pin_boxed::<PThreadMutex, _>(
+        MutexAttr::new().protocol(MutexProtocol::Protect(i32::MAX)),
+    )
+    .err()
+    .unwrap()
+    .handle_alloc_and_layout();
+    assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+}
+
+#[test]
+fn poison() {
+    let mutex = init::pin_boxed::pin_boxed::<Mutex<i32>, _>(());
+    let mutex = mutex.as_ref();
+
+    std::thread::scope(|s| {
+        let result = s.spawn(|| {
+            let mut guard = mutex.lock_poisoning().unwrap();
+            guard.as_mut().set(1);
+            panic!("poison the mutex");
+        });
+        assert!(result.join().is_err());
+    });
+
+    assert!(mutex.is_poisoned());
+    let guard = mutex.lock_poisoning().unwrap_err().into_inner();
+    assert_eq!(*guard, 1);
+    drop(guard);
+    assert!(matches!(
+        mutex.try_lock_poisoning(),
+        Err(TryLockError::Poisoned(_))
+    ));
+
+    // the non-poisoning api ignores the flag
+    assert_eq!(*mutex.lock(), 1);
+
+    mutex.clear_poison();
+    assert!(!mutex.is_poisoned());
+    let _guard = mutex.lock_poisoning().unwrap();
+    assert!(matches!(
+        mutex.try_lock_poisoning(),
+        Err(TryLockError::WouldBlock)
+    ));
+}
@@ -0,0 +1,61 @@
+//! Tracks whether a thread panicked while holding a lock
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+};
+
+use init::{PinCtor, PinInit};
+
+/// Whether a lock is poisoned
+pub(crate) struct Flag {
+    failed: AtomicBool,
+}
+
+/// If the thread was already panicking when the lock was acquired
+///
+/// A guard which is dropped while unwinding only poisons the lock if it was acquired before the panic
+#[derive(Clone, Copy)]
+pub(crate) struct Guard {
+    panicking: bool,
+}
+
+impl Flag {
+    pub(crate) const fn new() -> Self {
+        Self {
+            failed: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn get(&self) -> bool {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn clear(&self) {
+        self.failed.store(false, Ordering::Relaxed)
+    }
+
+    /// Called when the lock is acquired
+    pub(crate) fn guard(&self) -> Guard {
+        Guard {
+            panicking: thread::panicking(),
+        }
+    }
+
+    /// Called when the lock is released
+    pub(crate) fn done(&self, guard: Guard) {
+        if !guard.panicking && thread::panicking() {
+            self.failed.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl PinCtor for Flag {
+    fn pin_init(uninit: init::Uninit<'_, Self>, (): ()) -> PinInit<'_, Self> {
+        uninit.write(Self::new()).pin()
+    }
+}
+
+impl init::layout_provider::HasLayoutProvider for Flag {
+    type LayoutProvider = init::layout_provider::SizedLayoutProvider;
+}
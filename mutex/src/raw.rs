@@ -0,0 +1,107 @@
+//! The interface between [`Mutex`](crate::Mutex) and the lock which backs it
+
+use std::pin::Pin;
+
+use init::PinCtor;
+
+use crate::PThreadMutex;
+
+/// A lock which doesn't protect any data, and can back a [`Mutex`](crate::Mutex)
+///
+/// # Safety
+///
+/// A lock constructed through `PinCtor<()>` must provide mutual exclusion: after `lock` returns
+/// or `try_lock` returns true, no other call to `lock` or `try_lock` may succeed (even on the
+/// same thread) until `unlock` is called
+pub unsafe trait RawLock: PinCtor {
+    /// Acquire the lock, blocking until it's available
+    fn lock(self: Pin<&Self>);
+
+    /// Try to acquire the lock without blocking
+    fn try_lock(self: Pin<&Self>) -> bool;
+
+    /// Release the lock
+    ///
+    /// # Safety
+    ///
+    /// The lock must be currently held by this thread
+    unsafe fn unlock(self: Pin<&Self>);
+}
+
+// SAFETY: a `PThreadMutex` is only recursive if it's constructed with `MutexKind::Recursive`,
+// and `Mutex<T>` rejects that
+unsafe impl RawLock for PThreadMutex {
+    fn lock(self: Pin<&Self>) {
+        PThreadMutex::lock(self)
+    }
+
+    fn try_lock(self: Pin<&Self>) -> bool {
+        PThreadMutex::try_lock(self)
+    }
+
+    unsafe fn unlock(self: Pin<&Self>) {
+        // SAFETY: guaranteed by caller
+        unsafe { PThreadMutex::force_unlock(self) }
+    }
+}
+
+/// Adapts a [`lock_api::RawMutex`], such as `parking_lot::RawMutex`, so it can back a
+/// [`Mutex`](crate::Mutex)
+///
+/// In the other direction, [`FutexMutex`](crate::FutexMutex) implements `lock_api::RawMutex`,
+/// so it can be used with `lock_api` guards. [`PThreadMutex`](crate::PThreadMutex) can't,
+/// since a `lock_api::Mutex` may be moved whenever it's unlocked, while a `pthread_mutex_t`
+/// must stay at the same address once it's been used, and may need attributes which can't
+/// be set up in `lock_api::RawMutex::INIT`
+///
+/// ```
+/// # use mutex::{FutexMutex, LockApi, Mutex};
+/// let mutex = init::pin_boxed::pin_boxed::<Mutex<i32, LockApi<FutexMutex>>, _>(());
+/// *mutex.as_ref().lock().as_mut() += 1;
+/// ```
+#[cfg(feature = "lock_api")]
+pub struct LockApi<R>(R);
+
+#[cfg(feature = "lock_api")]
+impl<R: lock_api::RawMutex> LockApi<R> {
+    /// Create a new unlocked mutex
+    pub const fn new() -> Self {
+        Self(R::INIT)
+    }
+}
+
+#[cfg(feature = "lock_api")]
+impl<R: lock_api::RawMutex> Default for LockApi<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "lock_api")]
+impl<R: lock_api::RawMutex> PinCtor for LockApi<R> {
+    fn pin_init(uninit: init::Uninit<'_, Self>, (): ()) -> init::PinInit<'_, Self> {
+        uninit.write(Self::new()).pin()
+    }
+}
+
+#[cfg(feature = "lock_api")]
+impl<R: lock_api::RawMutex> init::layout_provider::HasLayoutProvider for LockApi<R> {
+    type LayoutProvider = init::layout_provider::SizedLayoutProvider;
+}
+
+// SAFETY: `lock_api::RawMutex` guarantees mutual exclusion
+#[cfg(feature = "lock_api")]
+unsafe impl<R: lock_api::RawMutex> RawLock for LockApi<R> {
+    fn lock(self: Pin<&Self>) {
+        self.get_ref().0.lock()
+    }
+
+    fn try_lock(self: Pin<&Self>) -> bool {
+        self.get_ref().0.try_lock()
+    }
+
+    unsafe fn unlock(self: Pin<&Self>) {
+        // SAFETY: guaranteed by caller
+        unsafe { self.get_ref().0.unlock() }
+    }
+}
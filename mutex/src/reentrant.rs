@@ -0,0 +1,221 @@
+//! A pinned mutex which the owning thread can lock again, built on a recursive `pthread_mutex_t`
+
+use std::{
+    alloc::Layout,
+    cell::{Cell, UnsafeCell},
+    marker::PhantomData,
+    ops::Deref,
+    pin::Pin,
+};
+
+use init::{
+    layout_provider::{HasLayoutProvider, LayoutProvider},
+    PinCtor, PinInit,
+};
+
+use crate::{MutexAttr, MutexKind, NewMutex, PThreadMutex};
+
+/// A mutex which the thread holding it can lock again without deadlocking
+///
+/// Since the same thread can hold several guards at once, the guards only give shared access
+/// to the value. Use a `Cell` or `RefCell` to modify it
+#[repr(C)]
+pub struct ReentrantMutex<T: ?Sized> {
+    lock: PThreadMutex,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: the value can be moved between threads with the lock
+unsafe impl<T: ?Sized + Send> Send for ReentrantMutex<T> {}
+// SAFETY: only the thread holding the lock can access the value
+unsafe impl<T: ?Sized + Send> Sync for ReentrantMutex<T> {}
+
+/// A lock on a [`ReentrantMutex`]
+pub struct ReentrantMutexGuard<'a, T: ?Sized> {
+    mutex: Pin<&'a ReentrantMutex<T>>,
+    _not_send: PhantomData<&'static Cell<()>>,
+}
+
+// SAFETY: the guard only gives shared access to the value
+unsafe impl<T: ?Sized + Sync> Sync for ReentrantMutexGuard<'_, T> {}
+
+impl<T: ?Sized> ReentrantMutex<T> {
+    /// Get the raw lock
+    pub fn as_lock(self: Pin<&Self>) -> Pin<&PThreadMutex> {
+        // SAFETY: the lock is structurally pinned
+        unsafe { self.map_unchecked(|this| &this.lock) }
+    }
+
+    /// Acquire the lock, blocking until it's available or returning immediately
+    /// if this thread already holds it
+    pub fn lock(self: Pin<&Self>) -> ReentrantMutexGuard<'_, T> {
+        self.as_lock().lock();
+        ReentrantMutexGuard {
+            mutex: self,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Try to acquire the lock without blocking, which succeeds if this thread already holds it
+    pub fn try_lock(self: Pin<&Self>) -> Option<ReentrantMutexGuard<'_, T>> {
+        if self.as_lock().try_lock() {
+            Some(ReentrantMutexGuard {
+                mutex: self,
+                _not_send: PhantomData,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: ?Sized> ReentrantMutexGuard<'_, T> {
+    pub fn as_ref(&self) -> Pin<&T> {
+        // SAFETY: the value is structurally pinned, and the lock is held
+        unsafe { Pin::new_unchecked(&*self.mutex.value.get()) }
+    }
+}
+
+impl<T: ?Sized> Deref for ReentrantMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the lock is held
+        unsafe { &*self.mutex.value.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for ReentrantMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the guard holds one level of the lock
+        unsafe { self.mutex.as_lock().force_unlock() }
+    }
+}
+
+impl<T: ?Sized + core::fmt::Debug> core::fmt::Debug for ReentrantMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+const RECURSIVE: MutexAttr = MutexAttr::new().kind(MutexKind::Recursive);
+
+impl<T: ?Sized + PinCtor> PinCtor for ReentrantMutex<T> {
+    fn pin_init(uninit: init::Uninit<'_, Self>, (): ()) -> PinInit<'_, Self> {
+        init::pin_init_struct! {
+            uninit => Self {
+                lock: RECURSIVE,
+                value: init::ext::NewUnsafeCell(())
+            }
+        }
+    }
+}
+
+impl<T: ?Sized + PinCtor<A>, A> PinCtor<NewMutex<A>> for ReentrantMutex<T> {
+    fn pin_init(uninit: init::Uninit<'_, Self>, NewMutex(args): NewMutex<A>) -> PinInit<'_, Self> {
+        init::pin_init_struct! {
+            uninit => Self {
+                lock: RECURSIVE,
+                value: init::ext::NewUnsafeCell(args)
+            }
+        }
+    }
+}
+
+pub struct ReentrantMutexLayoutProvider;
+
+impl<T: ?Sized + HasLayoutProvider> HasLayoutProvider for ReentrantMutex<T> {
+    type LayoutProvider = ReentrantMutexLayoutProvider;
+}
+
+impl<T: ?Sized + HasLayoutProvider<A>, A> HasLayoutProvider<NewMutex<A>> for ReentrantMutex<T> {
+    type LayoutProvider = ReentrantMutexLayoutProvider;
+}
+
+fn layout_of<T: ?Sized + HasLayoutProvider<A>, A>(args: &A) -> Option<Layout> {
+    let lock = Layout::new::<PThreadMutex>();
+    let value = init::layout_provider::layout_of::<T, A>(args)?;
+    Some(lock.extend(value).ok()?.0.pad_to_align())
+}
+
+/// # Safety
+///
+/// `ptr` must be valid for the layout returned by `layout_of::<T, A>(args)`
+unsafe fn cast<T: ?Sized + HasLayoutProvider<A>, A>(
+    ptr: std::ptr::NonNull<u8>,
+    args: &A,
+) -> std::ptr::NonNull<ReentrantMutex<T>> {
+    // SAFETY: guaranteed by caller
+    let ptr = unsafe { init::layout_provider::cast::<T, A>(ptr, args) };
+    // SAFETY: `ptr` is non-null, and the cast keeps the metadata of `T`
+    unsafe { std::ptr::NonNull::new_unchecked(ptr.as_ptr() as *mut ReentrantMutex<T>) }
+}
+
+// SAFETY: the layout and metadata both come from `T`'s layout provider
+unsafe impl<T: ?Sized + HasLayoutProvider> LayoutProvider<ReentrantMutex<T>>
+    for ReentrantMutexLayoutProvider
+{
+    fn layout_of((): &()) -> Option<Layout> {
+        layout_of::<T, ()>(&())
+    }
+
+    unsafe fn cast(ptr: std::ptr::NonNull<u8>, (): &()) -> std::ptr::NonNull<ReentrantMutex<T>> {
+        // SAFETY: guaranteed by caller
+        unsafe { cast::<T, ()>(ptr, &()) }
+    }
+}
+
+// SAFETY: the layout and metadata both come from `T`'s layout provider
+unsafe impl<T: ?Sized + HasLayoutProvider<A>, A> LayoutProvider<ReentrantMutex<T>, NewMutex<A>>
+    for ReentrantMutexLayoutProvider
+{
+    fn layout_of(args: &NewMutex<A>) -> Option<Layout> {
+        layout_of::<T, A>(&args.0)
+    }
+
+    unsafe fn cast(
+        ptr: std::ptr::NonNull<u8>,
+        args: &NewMutex<A>,
+    ) -> std::ptr::NonNull<ReentrantMutex<T>> {
+        // SAFETY: guaranteed by caller
+        unsafe { cast::<T, A>(ptr, &args.0) }
+    }
+}
+
+#[test]
+fn reentrant() {
+    let value = init::pin_ctor(|uninit| uninit.write(Cell::new(0)).pin());
+    init::stack_pin_init(
+        NewMutex(value),
+        |mutex: Pin<&mut ReentrantMutex<Cell<i32>>>| {
+            let mutex = mutex.as_ref();
+
+            let a = mutex.lock();
+            let b = mutex.try_lock().unwrap();
+            a.set(1);
+            assert_eq!(b.get(), 1);
+
+            std::thread::scope(|s| {
+                s.spawn(|| assert!(mutex.try_lock().is_none()));
+            });
+
+            drop((a, b));
+            std::thread::scope(|s| {
+                s.spawn(|| mutex.lock().set(2));
+            });
+            assert_eq!(mutex.lock().get(), 2);
+        },
+    );
+}
+
+#[test]
+fn reentrant_unsized() {
+    let mutex = init::pin_boxed::pin_boxed::<ReentrantMutex<[u8]>, _>(NewMutex(
+        init::slice::pin_ctor::CopyArgsLen(3, 1),
+    ));
+    let mutex = mutex.as_ref();
+
+    let guard = mutex.lock();
+    assert_eq!(*guard, [1, 1, 1]);
+    assert_eq!(*mutex.lock(), [1, 1, 1]);
+}
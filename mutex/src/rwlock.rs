@@ -0,0 +1,515 @@
+//! A pinned reader-writer lock built on `pthread_rwlock_t`
+
+use std::{
+    alloc::Layout,
+    cell::{Cell, UnsafeCell},
+    marker::{PhantomData, PhantomPinned},
+    ops::Deref,
+    pin::Pin,
+};
+
+use init::{
+    layout_provider::{HasLayoutProvider, LayoutProvider},
+    PinCtor, PinInit,
+};
+use libc::{
+    pthread_rwlock_destroy, pthread_rwlock_init, pthread_rwlock_rdlock, pthread_rwlock_t,
+    pthread_rwlock_tryrdlock, pthread_rwlock_trywrlock, pthread_rwlock_unlock,
+    pthread_rwlock_wrlock, pthread_rwlockattr_destroy, pthread_rwlockattr_init,
+    pthread_rwlockattr_t,
+};
+
+// from glibc's pthread.h, these aren't exposed by the libc crate
+#[cfg(target_env = "gnu")]
+const PTHREAD_RWLOCK_PREFER_READER_NP: libc::c_int = 0;
+#[cfg(target_env = "gnu")]
+const PTHREAD_RWLOCK_PREFER_WRITER_NONRECURSIVE_NP: libc::c_int = 2;
+
+/// Which kind of waiter is woken first when a [`PThreadRwLock`] is unlocked
+///
+/// Only glibc lets the kind be chosen, other platforms always use their default
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RwLockKind {
+    /// Readers can acquire the lock while writers are waiting, which may starve writers
+    #[default]
+    PreferReader,
+    /// New readers block while a writer is waiting
+    ///
+    /// A thread which already holds a read lock must not acquire it again, since that
+    /// would deadlock with a waiting writer
+    #[cfg(target_env = "gnu")]
+    PreferWriter,
+}
+
+#[repr(transparent)]
+struct PThreadRwLockAttr {
+    value: pthread_rwlockattr_t,
+    _unpin: PhantomPinned,
+}
+
+impl Drop for PThreadRwLockAttr {
+    fn drop(&mut self) {
+        // SAFETY: the attribute object was initialized in `pin_init`
+        unsafe { pthread_rwlockattr_destroy(&mut self.value) };
+    }
+}
+
+impl PinCtor<RwLockKind> for PThreadRwLockAttr {
+    fn pin_init(mut uninit: init::Uninit<'_, Self>, kind: RwLockKind) -> PinInit<'_, Self> {
+        let ptr = uninit.as_mut_ptr().cast();
+        // SAFETY: `ptr` is valid for writes
+        let err = unsafe { pthread_rwlockattr_init(ptr) };
+        assert_eq!(err, 0);
+        #[cfg(target_env = "gnu")]
+        {
+            let kind = match kind {
+                RwLockKind::PreferReader => PTHREAD_RWLOCK_PREFER_READER_NP,
+                RwLockKind::PreferWriter => PTHREAD_RWLOCK_PREFER_WRITER_NONRECURSIVE_NP,
+            };
+            // SAFETY: the attribute object was just initialized
+            let err = unsafe { libc::pthread_rwlockattr_setkind_np(ptr, kind) };
+            assert_eq!(err, 0);
+        }
+        #[cfg(not(target_env = "gnu"))]
+        let RwLockKind::PreferReader = kind;
+        // SAFETY: the attribute object was initialized above
+        unsafe { uninit.assume_init().pin() }
+    }
+}
+
+impl HasLayoutProvider<RwLockKind> for PThreadRwLockAttr {
+    type LayoutProvider = init::layout_provider::SizedLayoutProvider;
+}
+
+/// A raw reader-writer lock, which doesn't protect any data
+#[repr(transparent)]
+pub struct PThreadRwLock {
+    lock: UnsafeCell<pthread_rwlock_t>,
+    _unpin: PhantomPinned,
+}
+
+// SAFETY: `pthread_rwlock_t` may be used from any thread
+unsafe impl Send for PThreadRwLock {}
+// SAFETY: `pthread_rwlock_t` may be used from any thread
+unsafe impl Sync for PThreadRwLock {}
+
+impl Drop for PThreadRwLock {
+    fn drop(&mut self) {
+        // SAFETY: the lock is initialized, and can't be locked since guards borrow it
+        unsafe { pthread_rwlock_destroy(self.lock.get()) };
+    }
+}
+
+impl PinCtor for PThreadRwLock {
+    fn pin_init(uninit: init::Uninit<'_, Self>, (): ()) -> PinInit<'_, Self> {
+        Self::pin_init(uninit, RwLockKind::default())
+    }
+}
+
+impl PinCtor<RwLockKind> for PThreadRwLock {
+    fn pin_init(mut uninit: init::Uninit<'_, Self>, kind: RwLockKind) -> PinInit<'_, Self> {
+        init::stack_pin_init(kind, |attr: Pin<&mut PThreadRwLockAttr>| {
+            // SAFETY: `uninit` is valid for writes, and `attr` is initialized
+            let err = unsafe { pthread_rwlock_init(uninit.as_mut_ptr().cast(), &attr.value) };
+            assert_eq!(err, 0);
+            // SAFETY: the lock was initialized above
+            unsafe { uninit.assume_init().pin() }
+        })
+    }
+}
+
+impl HasLayoutProvider for PThreadRwLock {
+    type LayoutProvider = init::layout_provider::SizedLayoutProvider;
+}
+
+impl HasLayoutProvider<RwLockKind> for PThreadRwLock {
+    type LayoutProvider = init::layout_provider::SizedLayoutProvider;
+}
+
+impl Default for PThreadRwLock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PThreadRwLock {
+    /// Create a new reader-writer lock with the default attributes
+    pub const fn new() -> Self {
+        Self {
+            lock: UnsafeCell::new(libc::PTHREAD_RWLOCK_INITIALIZER),
+            _unpin: PhantomPinned,
+        }
+    }
+
+    /// Get a pointer to the underlying `pthread_rwlock_t`
+    pub fn as_raw(self: Pin<&Self>) -> *mut pthread_rwlock_t {
+        self.lock.get()
+    }
+
+    /// Acquire a shared lock, blocking until it's available
+    ///
+    /// # Panics
+    ///
+    /// If this thread already holds the lock, and the deadlock is detected (`EDEADLK`)
+    pub fn read(self: Pin<&Self>) {
+        // SAFETY: the lock is initialized and pinned
+        let val = unsafe { pthread_rwlock_rdlock(self.lock.get()) };
+        assert_eq!(val, 0);
+    }
+
+    /// Try to acquire a shared lock without blocking
+    pub fn try_read(self: Pin<&Self>) -> bool {
+        // SAFETY: the lock is initialized and pinned
+        0 == unsafe { pthread_rwlock_tryrdlock(self.lock.get()) }
+    }
+
+    /// Acquire an exclusive lock, blocking until it's available
+    ///
+    /// # Panics
+    ///
+    /// If this thread already holds the lock, and the deadlock is detected (`EDEADLK`)
+    pub fn write(self: Pin<&Self>) {
+        // SAFETY: the lock is initialized and pinned
+        let val = unsafe { pthread_rwlock_wrlock(self.lock.get()) };
+        assert_eq!(val, 0);
+    }
+
+    /// Try to acquire an exclusive lock without blocking
+    pub fn try_write(self: Pin<&Self>) -> bool {
+        // SAFETY: the lock is initialized and pinned
+        0 == unsafe { pthread_rwlock_trywrlock(self.lock.get()) }
+    }
+
+    /// Release a shared or exclusive lock
+    ///
+    /// # Safety
+    ///
+    /// The lock must be currently held by this thread
+    pub unsafe fn force_unlock(self: Pin<&Self>) {
+        // SAFETY: the lock is initialized, pinned, and held (guaranteed by caller)
+        let val = unsafe { pthread_rwlock_unlock(self.lock.get()) };
+        assert_eq!(val, 0);
+    }
+}
+
+/// A reader-writer lock which is initialized in place, and protects a pinned value
+#[repr(C)]
+pub struct RwLock<T: ?Sized> {
+    lock: PThreadRwLock,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: the value can be moved between threads with the lock
+unsafe impl<T: ?Sized + Send> Send for RwLock<T> {}
+// SAFETY: readers share the value between threads, and writers can send it between threads
+unsafe impl<T: ?Sized + Send + Sync> Sync for RwLock<T> {}
+
+/// A shared lock on a [`RwLock`]
+pub struct RwLockReadGuard<'a, T: ?Sized> {
+    lock: Pin<&'a RwLock<T>>,
+    _not_send: PhantomData<&'static Cell<()>>,
+}
+
+/// An exclusive lock on a [`RwLock`]
+pub struct RwLockWriteGuard<'a, T: ?Sized> {
+    lock: Pin<&'a RwLock<T>>,
+    _not_send: PhantomData<&'static Cell<()>>,
+}
+
+// SAFETY: the guard only gives shared access to the value
+unsafe impl<T: ?Sized + Sync> Sync for RwLockReadGuard<'_, T> {}
+// SAFETY: shared access to the guard only gives shared access to the value
+unsafe impl<T: ?Sized + Sync> Sync for RwLockWriteGuard<'_, T> {}
+
+impl<T> RwLock<T> {
+    /// Create a new reader-writer lock with the default attributes
+    pub const fn new(value: T) -> Self {
+        Self {
+            lock: PThreadRwLock::new(),
+            value: UnsafeCell::new(value),
+        }
+    }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    /// Get the raw lock
+    pub fn as_lock(self: Pin<&Self>) -> Pin<&PThreadRwLock> {
+        // SAFETY: the lock is structurally pinned
+        unsafe { self.map_unchecked(|this| &this.lock) }
+    }
+
+    /// Acquire a shared lock, blocking until it's available
+    ///
+    /// # Panics
+    ///
+    /// If this thread already holds the lock, and the deadlock is detected (`EDEADLK`)
+    pub fn read(self: Pin<&Self>) -> RwLockReadGuard<'_, T> {
+        self.as_lock().read();
+        RwLockReadGuard {
+            lock: self,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Try to acquire a shared lock without blocking
+    pub fn try_read(self: Pin<&Self>) -> Option<RwLockReadGuard<'_, T>> {
+        if self.as_lock().try_read() {
+            Some(RwLockReadGuard {
+                lock: self,
+                _not_send: PhantomData,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Acquire an exclusive lock, blocking until it's available
+    ///
+    /// # Panics
+    ///
+    /// If this thread already holds the lock, and the deadlock is detected (`EDEADLK`)
+    pub fn write(self: Pin<&Self>) -> RwLockWriteGuard<'_, T> {
+        self.as_lock().write();
+        RwLockWriteGuard {
+            lock: self,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Try to acquire an exclusive lock without blocking
+    pub fn try_write(self: Pin<&Self>) -> Option<RwLockWriteGuard<'_, T>> {
+        if self.as_lock().try_write() {
+            Some(RwLockWriteGuard {
+                lock: self,
+                _not_send: PhantomData,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: ?Sized> RwLockReadGuard<'_, T> {
+    pub fn as_ref(&self) -> Pin<&T> {
+        // SAFETY: the value is structurally pinned, and the lock is held
+        unsafe { Pin::new_unchecked(&*self.lock.value.get()) }
+    }
+}
+
+impl<T: ?Sized> RwLockWriteGuard<'_, T> {
+    pub fn as_ref(&self) -> Pin<&T> {
+        // SAFETY: the value is structurally pinned, and the lock is held
+        unsafe { Pin::new_unchecked(&*self.lock.value.get()) }
+    }
+
+    pub fn as_mut(&mut self) -> Pin<&mut T> {
+        // SAFETY: the value is structurally pinned, and the lock is held exclusively
+        unsafe { Pin::new_unchecked(&mut *self.lock.value.get()) }
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the lock is held
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the lock is held
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the guard holds the lock
+        unsafe { self.lock.as_lock().force_unlock() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the guard holds the lock
+        unsafe { self.lock.as_lock().force_unlock() }
+    }
+}
+
+impl<T: ?Sized + core::fmt::Debug> core::fmt::Debug for RwLockReadGuard<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized + core::fmt::Debug> core::fmt::Debug for RwLockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+/// Construct a [`RwLock`] with the default attributes, and initialize the value with `A`
+pub struct NewRwLock<A>(pub A);
+
+/// Construct a [`RwLock`] of the given kind, and initialize the value with `A`
+pub struct NewRwLockWith<A>(pub RwLockKind, pub A);
+
+impl<T: ?Sized + PinCtor> PinCtor for RwLock<T> {
+    fn pin_init(uninit: init::Uninit<'_, Self>, (): ()) -> PinInit<'_, Self> {
+        init::pin_init_struct! {
+            uninit => Self {
+                lock: (),
+                value: init::ext::NewUnsafeCell(())
+            }
+        }
+    }
+}
+
+impl<T: ?Sized + PinCtor<A>, A> PinCtor<NewRwLock<A>> for RwLock<T> {
+    fn pin_init(
+        uninit: init::Uninit<'_, Self>,
+        NewRwLock(args): NewRwLock<A>,
+    ) -> PinInit<'_, Self> {
+        init::pin_init_struct! {
+            uninit => Self {
+                lock: (),
+                value: init::ext::NewUnsafeCell(args)
+            }
+        }
+    }
+}
+
+impl<T: ?Sized + PinCtor<A>, A> PinCtor<NewRwLockWith<A>> for RwLock<T> {
+    fn pin_init(
+        uninit: init::Uninit<'_, Self>,
+        NewRwLockWith(kind, args): NewRwLockWith<A>,
+    ) -> PinInit<'_, Self> {
+        init::pin_init_struct! {
+            uninit => Self {
+                lock: kind,
+                value: init::ext::NewUnsafeCell(args)
+            }
+        }
+    }
+}
+
+pub struct RwLockLayoutProvider;
+
+impl<T: ?Sized + HasLayoutProvider> HasLayoutProvider for RwLock<T> {
+    type LayoutProvider = RwLockLayoutProvider;
+}
+
+impl<T: ?Sized + HasLayoutProvider<A>, A> HasLayoutProvider<NewRwLock<A>> for RwLock<T> {
+    type LayoutProvider = RwLockLayoutProvider;
+}
+
+impl<T: ?Sized + HasLayoutProvider<A>, A> HasLayoutProvider<NewRwLockWith<A>> for RwLock<T> {
+    type LayoutProvider = RwLockLayoutProvider;
+}
+
+fn layout_of<T: ?Sized + HasLayoutProvider<A>, A>(args: &A) -> Option<Layout> {
+    let lock = Layout::new::<PThreadRwLock>();
+    let value = init::layout_provider::layout_of::<T, A>(args)?;
+    Some(lock.extend(value).ok()?.0.pad_to_align())
+}
+
+/// # Safety
+///
+/// `ptr` must be valid for the layout returned by `layout_of::<T, A>(args)`
+unsafe fn cast<T: ?Sized + HasLayoutProvider<A>, A>(
+    ptr: std::ptr::NonNull<u8>,
+    args: &A,
+) -> std::ptr::NonNull<RwLock<T>> {
+    // SAFETY: guaranteed by caller
+    let ptr = unsafe { init::layout_provider::cast::<T, A>(ptr, args) };
+    // SAFETY: `ptr` is non-null, and the cast keeps the metadata of `T`
+    unsafe { std::ptr::NonNull::new_unchecked(ptr.as_ptr() as *mut RwLock<T>) }
+}
+
+// SAFETY: the layout and metadata both come from `T`'s layout provider
+unsafe impl<T: ?Sized + HasLayoutProvider> LayoutProvider<RwLock<T>> for RwLockLayoutProvider {
+    fn layout_of((): &()) -> Option<Layout> {
+        layout_of::<T, ()>(&())
+    }
+
+    unsafe fn cast(ptr: std::ptr::NonNull<u8>, (): &()) -> std::ptr::NonNull<RwLock<T>> {
+        // SAFETY: guaranteed by caller
+        unsafe { cast::<T, ()>(ptr, &()) }
+    }
+}
+
+// SAFETY: the layout and metadata both come from `T`'s layout provider
+unsafe impl<T: ?Sized + HasLayoutProvider<A>, A> LayoutProvider<RwLock<T>, NewRwLock<A>>
+    for RwLockLayoutProvider
+{
+    fn layout_of(args: &NewRwLock<A>) -> Option<Layout> {
+        layout_of::<T, A>(&args.0)
+    }
+
+    unsafe fn cast(
+        ptr: std::ptr::NonNull<u8>,
+        args: &NewRwLock<A>,
+    ) -> std::ptr::NonNull<RwLock<T>> {
+        // SAFETY: guaranteed by caller
+        unsafe { cast::<T, A>(ptr, &args.0) }
+    }
+}
+
+// SAFETY: the layout and metadata both come from `T`'s layout provider
+unsafe impl<T: ?Sized + HasLayoutProvider<A>, A> LayoutProvider<RwLock<T>, NewRwLockWith<A>>
+    for RwLockLayoutProvider
+{
+    fn layout_of(args: &NewRwLockWith<A>) -> Option<Layout> {
+        layout_of::<T, A>(&args.1)
+    }
+
+    unsafe fn cast(
+        ptr: std::ptr::NonNull<u8>,
+        args: &NewRwLockWith<A>,
+    ) -> std::ptr::NonNull<RwLock<T>> {
+        // SAFETY: guaranteed by caller
+        unsafe { cast::<T, A>(ptr, &args.1) }
+    }
+}
+
+#[test]
+fn rwlock() {
+    let lock = init::pin_boxed::pin_boxed::<RwLock<i32>, _>(());
+    let lock = lock.as_ref();
+
+    let a = lock.read();
+    let b = lock.try_read().unwrap();
+    assert!(lock.try_write().is_none());
+    assert_eq!((*a, *b), (0, 0));
+    drop((a, b));
+
+    let mut guard = lock.write();
+    guard.as_mut().set(3);
+    assert!(lock.try_read().is_none());
+    assert!(lock.try_write().is_none());
+    drop(guard);
+
+    assert_eq!(*lock.read(), 3);
+}
+
+#[test]
+fn rwlock_unsized() {
+    #[cfg(target_env = "gnu")]
+    let kind = RwLockKind::PreferWriter;
+    #[cfg(not(target_env = "gnu"))]
+    let kind = RwLockKind::PreferReader;
+
+    let lock = init::pin_boxed::pin_boxed::<RwLock<[u8]>, _>(NewRwLockWith(
+        kind,
+        init::slice::pin_ctor::CopyArgsLen(3, 1),
+    ));
+    let lock = lock.as_ref();
+
+    assert_eq!(*lock.read(), [1, 1, 1]);
+    lock.write().as_mut().get_mut()[1] = 2;
+    assert_eq!(*lock.try_read().unwrap(), [1, 2, 1]);
+}
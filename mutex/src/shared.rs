@@ -0,0 +1,187 @@
+//! A [`Mutex`] which lives in memory shared with other processes
+
+use std::{
+    io, mem,
+    os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd},
+    pin::Pin,
+    ptr::{null_mut, NonNull},
+};
+
+use init::PinCtor;
+
+use crate::{Mutex, MutexAttr, NewMutexError, NewMutexWith};
+
+/// A [`Mutex`] in a `MAP_SHARED` mapping of a memfd, which other processes can lock
+///
+/// The mapping is inherited by child processes created with `fork`, and the file descriptor
+/// can be sent to unrelated processes, which map it with [`from_fd`](Self::from_fd)
+///
+/// The value must be `Copy`, since it can't refer to memory which is private to one process.
+/// The mutex itself is never destroyed, because other processes may still be using it when
+/// this handle is dropped, so dropping the handle only unmaps it
+pub struct SharedMutex<T> {
+    mutex: NonNull<Mutex<T>>,
+    fd: OwnedFd,
+}
+
+// SAFETY: the value can be moved between threads with the mapping, which isn't tied to a thread
+unsafe impl<T: Send> Send for SharedMutex<T> {}
+// SAFETY: the handle only gives access to the mutex, which only gives access to the value
+// while it's locked
+unsafe impl<T: Send> Sync for SharedMutex<T> {}
+
+fn map<T>(fd: BorrowedFd<'_>) -> io::Result<NonNull<Mutex<T>>> {
+    // SAFETY: `sysconf` has no preconditions
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if mem::align_of::<Mutex<T>>() > page_size as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the mutex is aligned to more than a page",
+        ));
+    }
+
+    // SAFETY: creating a new mapping doesn't affect any existing memory
+    let ptr = unsafe {
+        libc::mmap(
+            null_mut(),
+            mem::size_of::<Mutex<T>>(),
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED,
+            fd.as_raw_fd(),
+            0,
+        )
+    };
+
+    if ptr == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+
+    // SAFETY: `mmap` succeeded, so `ptr` is non-null and page aligned
+    Ok(unsafe { NonNull::new_unchecked(ptr.cast()) })
+}
+
+impl<T: Copy> SharedMutex<T> {
+    /// Create a process-shared mutex in a new memfd, and initialize the value with `args`
+    pub fn new<A>(args: A) -> io::Result<Self>
+    where
+        T: PinCtor<A>,
+    {
+        Self::with_attr(MutexAttr::new(), args)
+    }
+
+    /// Create a mutex with the given attributes in a new memfd, and initialize the value with `args`
+    ///
+    /// The attributes are always made [`process_shared`](MutexAttr::process_shared), and making
+    /// them [`robust`](MutexAttr::robust) lets the other processes recover if one of them
+    /// exits while holding the lock
+    pub fn with_attr<A>(attr: MutexAttr, args: A) -> io::Result<Self>
+    where
+        T: PinCtor<A>,
+    {
+        // SAFETY: the name is a valid C string
+        let fd = unsafe { libc::memfd_create(c"mutex".as_ptr(), libc::MFD_CLOEXEC) };
+        if fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `memfd_create` returned a new file descriptor, which isn't owned by anything else
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let len = mem::size_of::<Mutex<T>>() as libc::off_t;
+        // SAFETY: `fd` is a valid file descriptor
+        if unsafe { libc::ftruncate(fd.as_raw_fd(), len) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let this = Self {
+            mutex: map(fd.as_fd())?,
+            fd,
+        };
+
+        // SAFETY: the mapping is large enough and aligned for a `Mutex<T>`, and nothing else
+        // refers to it yet
+        let uninit = unsafe { init::Uninit::from_raw(this.mutex.as_ptr()) };
+        let args = NewMutexWith(
+            attr.process_shared(true),
+            init::try_pin_ctor::of_pin_ctor(args),
+        );
+        let init = uninit.try_pin_init(args).map_err(|err| match err {
+            NewMutexError::Lock(err) => err,
+            NewMutexError::Value(inf) => match inf {},
+        })?;
+        // the mutex is never dropped, see above
+        init.take_ownership();
+
+        Ok(this)
+    }
+
+    /// Map a mutex which another process created with [`new`](Self::new)
+    /// or [`with_attr`](Self::with_attr)
+    ///
+    /// # Safety
+    ///
+    /// `fd` must refer to the memfd of a `SharedMutex<T>`, with the same `T`
+    pub unsafe fn from_fd(fd: OwnedFd) -> io::Result<Self> {
+        Ok(Self {
+            mutex: map(fd.as_fd())?,
+            fd,
+        })
+    }
+}
+
+impl<T> SharedMutex<T> {
+    /// Get the shared mutex
+    pub fn as_ref(&self) -> Pin<&Mutex<T>> {
+        // SAFETY: the mutex was initialized, and stays mapped until `self` is dropped
+        unsafe { Pin::new_unchecked(self.mutex.as_ref()) }
+    }
+}
+
+impl<T> AsFd for SharedMutex<T> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl<T> Drop for SharedMutex<T> {
+    fn drop(&mut self) {
+        // SAFETY: the mapping was created in `map` with the same length, and isn't used anymore
+        unsafe { libc::munmap(self.mutex.as_ptr().cast(), mem::size_of::<Mutex<T>>()) };
+    }
+}
+
+#[test]
+fn shared_mutex() {
+    let mutex = SharedMutex::<i32>::new(()).unwrap();
+    let mutex = mutex.as_ref();
+
+    // SAFETY: the child only locks the mutex and exits, without returning to the test harness
+    let pid = unsafe { libc::fork() };
+    assert_ne!(pid, -1);
+
+    let work = || {
+        for _ in 0..1000 {
+            let mut guard = mutex.lock();
+            let value = *guard;
+            std::thread::yield_now();
+            guard.as_mut().set(value + 1);
+        }
+    };
+
+    if pid == 0 {
+        // a panic must not unwind into the test harness in the child
+        let code = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(work)) {
+            Ok(()) => 0,
+            Err(_) => 1,
+        };
+        // SAFETY: exit the child without running the test harness
+        unsafe { libc::_exit(code) }
+    }
+
+    work();
+
+    let mut status = 0;
+    // SAFETY: `pid` is the child created above
+    assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+    assert!(libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0);
+    assert_eq!(*mutex.lock(), 2000);
+}
@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use libc::{clock_gettime, clockid_t, time_t, timespec};
+
+const NANOS_PER_SEC: libc::c_long = 1_000_000_000;
+
+/// Get the current time of `clock`
+pub(crate) fn now(clock: clockid_t) -> timespec {
+    let mut now = timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `now` is valid for writes
+    let err = unsafe { clock_gettime(clock, &mut now) };
+    assert_eq!(err, 0);
+    now
+}
+
+/// Get the absolute time `dur` after `start`, saturating instead of overflowing
+pub(crate) fn add(start: timespec, dur: Duration) -> timespec {
+    let secs = time_t::try_from(dur.as_secs()).unwrap_or(time_t::MAX);
+    let mut tv_sec = start.tv_sec.saturating_add(secs);
+    let mut tv_nsec = start.tv_nsec + libc::c_long::from(dur.subsec_nanos());
+
+    if tv_nsec >= NANOS_PER_SEC {
+        tv_nsec -= NANOS_PER_SEC;
+        tv_sec = tv_sec.saturating_add(1);
+    }
+
+    timespec { tv_sec, tv_nsec }
+}
+
+/// Get the absolute time of `clock` which is `dur` from now
+pub(crate) fn deadline(clock: clockid_t, dur: Duration) -> timespec {
+    add(now(clock), dur)
+}
@@ -0,0 +1,14 @@
+fn main() {
+    println!("cargo:rerun-if-changed=ffi/handle.c");
+
+    // the C half of the FFI tests, it's only linked into the tests which declare it with `#[link]`
+    cc::Build::new()
+        .file("ffi/handle.c")
+        .cargo_metadata(false)
+        .compile("thin_ffi_handle");
+
+    println!(
+        "cargo:rustc-link-search=native={}",
+        std::env::var("OUT_DIR").unwrap()
+    );
+}
@@ -0,0 +1,17 @@
+#include <stddef.h>
+
+/* a C library which only sees thin handles as opaque pointers */
+
+struct holder {
+    void *handle;
+};
+
+void *thin_ffi_roundtrip(void *handle) {
+    struct holder holder = { handle };
+    volatile struct holder *escaped = &holder;
+    return escaped->handle;
+}
+
+size_t thin_ffi_call(void *handle, size_t (*callback)(void *)) {
+    return callback(handle);
+}
@@ -0,0 +1,3 @@
+//! Tests which pass `thin` handles through C code
+//!
+//! These live in their own crate so that only the tests need a C compiler, not users of `thin`
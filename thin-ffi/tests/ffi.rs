@@ -0,0 +1,55 @@
+//! Round-trip thin handles through C code, which only sees them as `void*`
+
+use std::ffi::c_void;
+
+use thin::{boxed::ThinBox, vec::ThinVec};
+
+#[link(name = "thin_ffi_handle", kind = "static")]
+extern "C" {
+    fn thin_ffi_roundtrip(handle: *mut c_void) -> *mut c_void;
+    fn thin_ffi_call(handle: *mut c_void, callback: extern "C" fn(*mut c_void) -> usize) -> usize;
+}
+
+#[test]
+fn test_box_roundtrip() {
+    let bx = ThinBox::<str>::from("hello from C");
+    let handle = bx.into_raw_erased();
+
+    // SAFETY: the C function returns the handle it was given
+    let handle = unsafe { thin_ffi_roundtrip(handle) };
+    // SAFETY: the handle came from `ThinBox::<str>::into_raw_erased`
+    let bx = unsafe { ThinBox::<str>::from_raw_erased(handle) };
+
+    assert_eq!(&*bx, "hello from C");
+}
+
+#[test]
+fn test_vec_roundtrip() {
+    extern "C" fn sum(handle: *mut c_void) -> usize {
+        // SAFETY: the handle came from `ThinVec::<usize>::into_raw_erased`, and is
+        // turned back into a handle before returning
+        let vec = unsafe { ThinVec::<usize>::from_raw_erased(handle) };
+        let sum = vec.as_slice().iter().sum();
+        let _ = vec.into_raw_erased();
+        sum
+    }
+
+    let mut vec = ThinVec::<usize>::new();
+    vec.extend_from_slice(&[1, 2, 3, 4]);
+    let handle = vec.into_raw_erased();
+
+    // SAFETY: the C function passes the handle to `sum`
+    assert_eq!(unsafe { thin_ffi_call(handle, sum) }, 10);
+
+    // SAFETY: the C function returns the handle it was given
+    let handle = unsafe { thin_ffi_roundtrip(handle) };
+    // SAFETY: the handle came from `ThinVec::<usize>::into_raw_erased`
+    let vec = unsafe { ThinVec::<usize>::from_raw_erased(handle) };
+    assert_eq!(vec.as_slice(), [1, 2, 3, 4]);
+
+    // empty vectors don't allocate, but still round-trip
+    let handle = ThinVec::<usize>::new().into_raw_erased();
+    // SAFETY: the handle came from `ThinVec::<usize>::into_raw_erased`
+    let vec = unsafe { ThinVec::<usize>::from_raw_erased(thin_ffi_roundtrip(handle)) };
+    assert!(vec.is_empty());
+}
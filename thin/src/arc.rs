@@ -0,0 +1,340 @@
+//! A thread-safe reference counted thin pointer
+
+use core::{
+    fmt,
+    marker::PhantomData,
+    ops::Deref,
+    sync::atomic::{self, AtomicUsize, Ordering},
+};
+
+use init::{ctor::CloneCtor, layout_provider::HasLayoutProvider, source::Cloned, Ctor};
+
+use crate::{
+    ptr::RawThinPtr,
+    rc_box::{self, RcBox},
+};
+
+type Inner<T> = RcBox<AtomicUsize, T>;
+
+/// A soft limit on the reference counts, so that they can never overflow
+const MAX_REFCOUNT: usize = isize::MAX as usize;
+
+/// The weak count is set to this while `ThinArc::is_unique` checks the strong count
+const LOCKED: usize = usize::MAX;
+
+/// A type that's like an `Arc` but guaranteed to be the same representation as a `*mut ()`
+///
+/// The reference counts and the pointer metadata are stored in the allocation
+#[repr(transparent)]
+pub struct ThinArc<T: ?Sized> {
+    ptr: RawThinPtr<Inner<T>>,
+    ty: PhantomData<Inner<T>>,
+}
+
+/// A weak reference to a [`ThinArc`], which doesn't keep the value alive
+#[repr(transparent)]
+pub struct ThinWeak<T: ?Sized> {
+    ptr: RawThinPtr<Inner<T>>,
+    ty: PhantomData<Inner<T>>,
+}
+
+// SAFETY: `ThinArc` shares `T` across threads like an `Arc<T>`
+unsafe impl<T: ?Sized + Send + Sync> Send for ThinArc<T> {}
+// SAFETY: `ThinArc` shares `T` across threads like an `Arc<T>`
+unsafe impl<T: ?Sized + Send + Sync> Sync for ThinArc<T> {}
+// SAFETY: `ThinWeak` can be upgraded to a `ThinArc`
+unsafe impl<T: ?Sized + Send + Sync> Send for ThinWeak<T> {}
+// SAFETY: `ThinWeak` can be upgraded to a `ThinArc`
+unsafe impl<T: ?Sized + Send + Sync> Sync for ThinWeak<T> {}
+
+#[cold]
+#[inline(never)]
+fn refcount_overflow(count: &AtomicUsize) -> ! {
+    count.fetch_sub(1, Ordering::Relaxed);
+    panic!("reference count overflowed")
+}
+
+impl<T: ?Sized> ThinArc<T> {
+    /// Construct a new ThinArc
+    pub fn new<Args>(args: Args) -> Self
+    where
+        T: Ctor<Args> + HasLayoutProvider<Args>,
+    {
+        Self {
+            ptr: rc_box::new(args),
+            ty: PhantomData,
+        }
+    }
+
+    fn inner(&self) -> &Inner<T> {
+        // SAFETY: the allocation is kept alive by this strong pointer
+        unsafe { &*self.ptr.as_ptr() }
+    }
+
+    /// The number of strong pointers to this allocation
+    pub fn strong_count(this: &Self) -> usize {
+        this.inner().strong.load(Ordering::Acquire)
+    }
+
+    /// The number of weak pointers to this allocation
+    pub fn weak_count(this: &Self) -> usize {
+        match this.inner().weak.load(Ordering::Acquire) {
+            // the weak count is only locked by a unique `ThinArc`
+            LOCKED => 0,
+            // strong pointers collectively hold one weak count
+            weak => weak - 1,
+        }
+    }
+
+    /// Checks if both pointers point to the same allocation
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.ptr.as_erased_ptr() == other.ptr.as_erased_ptr()
+    }
+
+    /// Create a new weak pointer to this allocation
+    pub fn downgrade(this: &Self) -> ThinWeak<T> {
+        let weak = &this.inner().weak;
+        let mut current = weak.load(Ordering::Relaxed);
+
+        loop {
+            if current == LOCKED {
+                core::hint::spin_loop();
+                current = weak.load(Ordering::Relaxed);
+                continue;
+            }
+
+            if current > MAX_REFCOUNT {
+                panic!("reference count overflowed")
+            }
+
+            match weak.compare_exchange_weak(
+                current,
+                current + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    return ThinWeak {
+                        ptr: this.ptr,
+                        ty: PhantomData,
+                    }
+                }
+                Err(old) => current = old,
+            }
+        }
+    }
+
+    fn is_unique(this: &Self) -> bool {
+        // lock the weak count, so that no new weak pointers can be created
+        // while checking the strong count
+        if this
+            .inner()
+            .weak
+            .compare_exchange(1, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+
+        let unique = this.inner().strong.load(Ordering::Acquire) == 1;
+        this.inner().weak.store(1, Ordering::Release);
+        unique
+    }
+
+    /// Get a mutable reference to the value, if there are no other `ThinArc` or `ThinWeak` pointers
+    pub fn get_mut(this: &mut Self) -> Option<&mut T> {
+        if Self::is_unique(this) {
+            // SAFETY: there are no other pointers to the allocation
+            Some(unsafe { &mut *core::ptr::addr_of_mut!((*this.ptr.as_mut_ptr()).value) })
+        } else {
+            None
+        }
+    }
+
+    /// Get a mutable reference to the value
+    ///
+    /// If there are other `ThinArc` or `ThinWeak` pointers to this allocation, then
+    /// the value is cloned into a new allocation first with [`CloneCtor`]
+    pub fn make_mut(this: &mut Self) -> &mut T
+    where
+        T: CloneCtor,
+    {
+        if !Self::is_unique(this) {
+            *this = Self::new(Cloned(&**this));
+        }
+
+        // SAFETY: `this` is unique, so the only way to create another pointer is through `this`
+        unsafe { &mut *core::ptr::addr_of_mut!((*this.ptr.as_mut_ptr()).value) }
+    }
+}
+
+impl<T: ?Sized> Clone for ThinArc<T> {
+    fn clone(&self) -> Self {
+        let strong = &self.inner().strong;
+
+        if strong.fetch_add(1, Ordering::Relaxed) > MAX_REFCOUNT {
+            refcount_overflow(strong)
+        }
+
+        Self {
+            ptr: self.ptr,
+            ty: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Drop for ThinArc<T> {
+    fn drop(&mut self) {
+        if self.inner().strong.fetch_sub(1, Ordering::Release) != 1 {
+            return;
+        }
+
+        // synchronize with all other strong pointers being dropped
+        atomic::fence(Ordering::Acquire);
+
+        // release the weak count held by all strong pointers, even if dropping the value panics
+        let _weak = ThinWeak {
+            ptr: self.ptr,
+            ty: PhantomData,
+        };
+
+        // SAFETY: this was the last strong pointer, so nothing else can access the value
+        unsafe { core::ptr::addr_of_mut!((*self.ptr.as_mut_ptr()).value).drop_in_place() }
+    }
+}
+
+impl<T: ?Sized> Deref for ThinArc<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner().value
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ThinArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for ThinArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized> ThinWeak<T> {
+    fn counts(&self) -> (&AtomicUsize, &AtomicUsize) {
+        // SAFETY: the allocation is kept alive by this weak pointer, and the counts
+        // are valid even if the value was dropped
+        unsafe {
+            let ptr = self.ptr.as_ptr();
+            (
+                &*core::ptr::addr_of!((*ptr).strong),
+                &*core::ptr::addr_of!((*ptr).weak),
+            )
+        }
+    }
+
+    /// Try to get a strong pointer to the value, if it hasn't been dropped yet
+    pub fn upgrade(&self) -> Option<ThinArc<T>> {
+        let (strong, _) = self.counts();
+        let mut current = strong.load(Ordering::Relaxed);
+
+        loop {
+            if current == 0 {
+                return None;
+            }
+
+            if current > MAX_REFCOUNT {
+                panic!("reference count overflowed")
+            }
+
+            match strong.compare_exchange_weak(
+                current,
+                current + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    return Some(ThinArc {
+                        ptr: self.ptr,
+                        ty: PhantomData,
+                    })
+                }
+                Err(old) => current = old,
+            }
+        }
+    }
+
+    /// The number of strong pointers to this allocation
+    pub fn strong_count(&self) -> usize {
+        self.counts().0.load(Ordering::Acquire)
+    }
+}
+
+impl<T: ?Sized> Clone for ThinWeak<T> {
+    fn clone(&self) -> Self {
+        let (_, weak) = self.counts();
+
+        // the weak count can't be locked, since there is at least one weak pointer
+        if weak.fetch_add(1, Ordering::Relaxed) > MAX_REFCOUNT {
+            refcount_overflow(weak)
+        }
+
+        Self {
+            ptr: self.ptr,
+            ty: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Drop for ThinWeak<T> {
+    fn drop(&mut self) {
+        let (_, weak) = self.counts();
+
+        if weak.fetch_sub(1, Ordering::Release) == 1 {
+            atomic::fence(Ordering::Acquire);
+            // SAFETY: this was the last pointer to the allocation
+            unsafe { rc_box::dealloc(self.ptr) }
+        }
+    }
+}
+
+#[test]
+fn test_arc_threads() {
+    let a = ThinArc::<[usize]>::new(init::slice::ctor::CopyArgsLen(100, 7));
+    let weak = ThinArc::downgrade(&a);
+
+    let handles = (0..4)
+        .map(|_| {
+            let a = a.clone();
+            std::thread::spawn(move || a.iter().sum::<usize>())
+        })
+        .collect::<alloc::vec::Vec<_>>();
+
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), 700);
+    }
+
+    assert_eq!(ThinArc::strong_count(&a), 1);
+    assert_eq!(ThinArc::weak_count(&a), 1);
+    drop(a);
+    assert!(weak.upgrade().is_none());
+}
+
+#[test]
+fn test_arc_make_mut() {
+    let mut a = ThinArc::<[u8]>::new(init::slice::ctor::CopyArgsLen(3, 0));
+    let b = a.clone();
+    let weak = ThinArc::downgrade(&b);
+
+    ThinArc::make_mut(&mut a)[0] = 1;
+    assert_eq!(*a, [1, 0, 0]);
+    assert_eq!(*b, [0, 0, 0]);
+    assert_eq!(ThinArc::weak_count(&a), 0);
+    assert!(ThinArc::get_mut(&mut a).is_some());
+
+    drop(b);
+    assert!(weak.upgrade().is_none());
+}
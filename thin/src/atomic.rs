@@ -0,0 +1,345 @@
+//! Atomic thin pointers, which can be swapped out from under concurrent readers
+//!
+//! Since a [`ThinBox`] is a single `*mut ()`, even for unsized types, it can be stored
+//! in an `AtomicPtr`. Readers protect the value they are reading with a hazard pointer,
+//! and writers wait for readers of the old value to finish before returning it.
+
+mod hazard;
+
+use core::{
+    fmt,
+    marker::PhantomData,
+    ops::Deref,
+    ptr::{null_mut, NonNull},
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+use crate::{boxed::ThinBox, ptr::RawThinPtr};
+
+/// An atomic [`ThinBox`] which may be empty
+pub struct AtomicOptionThinBox<T: ?Sized> {
+    ptr: AtomicPtr<()>,
+    ty: PhantomData<Option<ThinBox<T>>>,
+}
+
+/// An atomic [`ThinBox`]
+#[repr(transparent)]
+pub struct AtomicThinBox<T: ?Sized> {
+    inner: AtomicOptionThinBox<T>,
+}
+
+/// A reference to the value of an atomic thin pointer
+///
+/// The value won't be returned from [`swap`](AtomicThinBox::swap) (or dropped)
+/// until all guards which reference it are dropped
+pub struct Guard<'a, T: ?Sized> {
+    slot: &'static hazard::Slot,
+    ptr: RawThinPtr<T>,
+    lt: PhantomData<&'a T>,
+}
+
+// SAFETY: values can be swapped in from one thread and out from another, and
+// are shared by all readers
+unsafe impl<T: ?Sized + Send> Send for AtomicOptionThinBox<T> {}
+// SAFETY: values can be swapped in from one thread and out from another, and
+// are shared by all readers
+unsafe impl<T: ?Sized + Send + Sync> Sync for AtomicOptionThinBox<T> {}
+
+fn into_erased<T: ?Sized>(bx: Option<ThinBox<T>>) -> *mut () {
+    match bx {
+        Some(bx) => ThinBox::into_raw(bx).as_erased_mut_ptr(),
+        None => null_mut(),
+    }
+}
+
+/// # Safety
+///
+/// `ptr` must have come from `into_erased`, and must not be used after this call
+unsafe fn from_erased<T: ?Sized>(ptr: *mut ()) -> Option<ThinBox<T>> {
+    let ptr = NonNull::new(ptr)?;
+    // SAFETY: guaranteed by caller
+    Some(unsafe { ThinBox::from_raw(RawThinPtr::from_erased(ptr)) })
+}
+
+impl<T: ?Sized> Drop for AtomicOptionThinBox<T> {
+    fn drop(&mut self) {
+        // SAFETY: guards borrow `self`, so there are no readers left
+        drop(unsafe { from_erased::<T>(*self.ptr.get_mut()) })
+    }
+}
+
+impl<T: ?Sized> Default for AtomicOptionThinBox<T> {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl<T: ?Sized> AtomicOptionThinBox<T> {
+    /// Create a new atomic pointer
+    pub fn new(bx: Option<ThinBox<T>>) -> Self {
+        Self {
+            ptr: AtomicPtr::new(into_erased(bx)),
+            ty: PhantomData,
+        }
+    }
+
+    /// Create a new empty atomic pointer
+    pub const fn none() -> Self {
+        Self {
+            ptr: AtomicPtr::new(null_mut()),
+            ty: PhantomData,
+        }
+    }
+
+    /// Get the value out of the atomic pointer
+    pub fn into_inner(mut self) -> Option<ThinBox<T>> {
+        let ptr = core::mem::replace(self.ptr.get_mut(), null_mut());
+        // SAFETY: the pointer was removed from `self`
+        unsafe { from_erased(ptr) }
+    }
+
+    /// Load the current value
+    ///
+    /// The value can't be reclaimed until the returned guard is dropped
+    pub fn load(&self) -> Option<Guard<'_, T>> {
+        let slot = hazard::acquire();
+        let mut ptr = self.ptr.load(Ordering::Acquire);
+
+        loop {
+            let Some(raw) = NonNull::new(ptr) else {
+                slot.release();
+                return None;
+            };
+
+            slot.protect(ptr);
+
+            // the pointer is only protected if it wasn't swapped out before it was published
+            match self.ptr.load(Ordering::SeqCst) {
+                current if current == ptr => {
+                    return Some(Guard {
+                        slot,
+                        ptr: RawThinPtr::from_erased(raw),
+                        lt: PhantomData,
+                    })
+                }
+                current => ptr = current,
+            }
+        }
+    }
+
+    /// Replace the current value, and return the old value
+    ///
+    /// This waits until all guards which reference the old value are dropped,
+    /// so calling this while holding such a guard on the same thread will deadlock
+    pub fn swap(&self, bx: Option<ThinBox<T>>) -> Option<ThinBox<T>> {
+        let old = self.ptr.swap(into_erased(bx), Ordering::SeqCst);
+        Self::reclaim(old)
+    }
+
+    /// Replace the current value, and drop the old value
+    ///
+    /// See [`swap`](Self::swap) for when this blocks
+    pub fn store(&self, bx: Option<ThinBox<T>>) {
+        drop(self.swap(bx))
+    }
+
+    /// Take the current value, and leave the atomic pointer empty
+    ///
+    /// See [`swap`](Self::swap) for when this blocks
+    pub fn take(&self) -> Option<ThinBox<T>> {
+        self.swap(None)
+    }
+
+    /// Replace the current value with `new` if it is the same allocation as `current`
+    ///
+    /// On success, the old value is returned once all other guards which reference it are
+    /// dropped (see [`swap`](Self::swap)), otherwise `new` is returned
+    pub fn compare_exchange(
+        &self,
+        current: Option<Guard<'_, T>>,
+        new: Option<ThinBox<T>>,
+    ) -> Result<Option<ThinBox<T>>, Option<ThinBox<T>>> {
+        let current_ptr = current
+            .as_ref()
+            .map_or(null_mut(), |guard| guard.ptr.as_erased_mut_ptr());
+        let new = into_erased(new);
+
+        // `current` protects the allocation from being reused while comparing, so the
+        // comparison can't succeed spuriously
+        let result =
+            self.ptr
+                .compare_exchange(current_ptr, new, Ordering::SeqCst, Ordering::SeqCst);
+        drop(current);
+
+        match result {
+            Ok(old) => Ok(Self::reclaim(old)),
+            // SAFETY: `new` wasn't stored in `self`
+            Err(_) => Err(unsafe { from_erased(new) }),
+        }
+    }
+
+    /// Take ownership of a pointer which was just removed from an atomic pointer
+    fn reclaim(old: *mut ()) -> Option<ThinBox<T>> {
+        if !old.is_null() {
+            hazard::wait_unprotected(old);
+        }
+
+        // SAFETY: `old` is no longer reachable from `self`, and there are no readers left
+        unsafe { from_erased(old) }
+    }
+}
+
+impl<T: ?Sized> AtomicThinBox<T> {
+    /// Create a new atomic pointer
+    pub fn new(bx: ThinBox<T>) -> Self {
+        Self {
+            inner: AtomicOptionThinBox::new(Some(bx)),
+        }
+    }
+
+    /// Get the value out of the atomic pointer
+    pub fn into_inner(self) -> ThinBox<T> {
+        // SAFETY: an `AtomicThinBox` is never empty
+        unsafe { self.inner.into_inner().unwrap_unchecked() }
+    }
+
+    /// Load the current value
+    ///
+    /// The value can't be reclaimed until the returned guard is dropped
+    pub fn load(&self) -> Guard<'_, T> {
+        // SAFETY: an `AtomicThinBox` is never empty
+        unsafe { self.inner.load().unwrap_unchecked() }
+    }
+
+    /// Replace the current value, and return the old value
+    ///
+    /// This waits until all guards which reference the old value are dropped,
+    /// so calling this while holding such a guard on the same thread will deadlock
+    pub fn swap(&self, bx: ThinBox<T>) -> ThinBox<T> {
+        // SAFETY: an `AtomicThinBox` is never empty
+        unsafe { self.inner.swap(Some(bx)).unwrap_unchecked() }
+    }
+
+    /// Replace the current value, and drop the old value
+    ///
+    /// See [`swap`](Self::swap) for when this blocks
+    pub fn store(&self, bx: ThinBox<T>) {
+        drop(self.swap(bx))
+    }
+
+    /// Replace the current value with `new` if it is the same allocation as `current`
+    ///
+    /// On success, the old value is returned once all other guards which reference it are
+    /// dropped (see [`swap`](Self::swap)), otherwise `new` is returned
+    pub fn compare_exchange(
+        &self,
+        current: Guard<'_, T>,
+        new: ThinBox<T>,
+    ) -> Result<ThinBox<T>, ThinBox<T>> {
+        // SAFETY: an `AtomicThinBox` is never empty
+        unsafe {
+            match self.inner.compare_exchange(Some(current), Some(new)) {
+                Ok(old) => Ok(old.unwrap_unchecked()),
+                Err(new) => Err(new.unwrap_unchecked()),
+            }
+        }
+    }
+}
+
+impl<T: ?Sized> From<ThinBox<T>> for AtomicThinBox<T> {
+    fn from(bx: ThinBox<T>) -> Self {
+        Self::new(bx)
+    }
+}
+
+impl<T: ?Sized> From<Option<ThinBox<T>>> for AtomicOptionThinBox<T> {
+    fn from(bx: Option<ThinBox<T>>) -> Self {
+        Self::new(bx)
+    }
+}
+
+impl<T: ?Sized> Drop for Guard<'_, T> {
+    fn drop(&mut self) {
+        self.slot.release();
+    }
+}
+
+impl<T: ?Sized> Deref for Guard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the value can't be reclaimed while it's protected by `slot`
+        unsafe { &*self.ptr.as_ptr() }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for Guard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for Guard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+#[test]
+fn test_atomic() {
+    let atomic = AtomicThinBox::<[u8]>::new(ThinBox::new(init::slice::ctor::CopyArgsLen(3, 1)));
+    assert_eq!(
+        core::mem::size_of::<AtomicThinBox<[u8]>>(),
+        core::mem::size_of::<*mut ()>()
+    );
+
+    let guard = atomic.load();
+    assert_eq!(*guard, [1, 1, 1]);
+
+    let new = ThinBox::new(init::slice::ctor::CopyArgsLen(2, 2));
+    let old = atomic.compare_exchange(guard, new).ok().unwrap();
+    assert_eq!(*old, [1, 1, 1]);
+    assert_eq!(*atomic.load(), [2, 2]);
+
+    // a guard to a different allocation doesn't match
+    let other = AtomicThinBox::<[u8]>::new(ThinBox::new(init::slice::ctor::CopyArgsLen(2, 2)));
+    let new = ThinBox::new(init::slice::ctor::CopyArgsLen(1, 3));
+    let new = atomic.compare_exchange(other.load(), new).err().unwrap();
+    assert_eq!(*new, [3]);
+
+    atomic.store(new);
+    assert_eq!(*atomic.into_inner(), [3]);
+
+    let atomic = AtomicOptionThinBox::<str>::none();
+    assert!(atomic.load().is_none());
+    assert!(atomic.swap(Some(ThinBox::from("hello"))).is_none());
+    assert_eq!(atomic.load().as_deref(), Some("hello"));
+    assert_eq!(atomic.take().as_deref(), Some("hello"));
+    assert!(atomic.take().is_none());
+}
+
+#[test]
+fn test_atomic_threads() {
+    let atomic = AtomicThinBox::<[usize]>::new(ThinBox::new(init::slice::ctor::CopyArgsLen(8, 0)));
+
+    std::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..1000 {
+                    let guard = atomic.load();
+                    // every value is a run of the same number
+                    assert!(guard.iter().all(|&x| x == guard[0]));
+                }
+            });
+        }
+
+        s.spawn(|| {
+            for i in 1..1000 {
+                let old = atomic.swap(ThinBox::new(init::slice::ctor::CopyArgsLen(8, i)));
+                assert!(old.iter().all(|&x| x == i - 1));
+            }
+        });
+    });
+
+    assert!(atomic.load().iter().all(|&x| x == 999));
+}
@@ -0,0 +1,91 @@
+//! A minimal hazard pointer scheme
+//!
+//! Readers publish the pointer they are about to dereference in a hazard slot, and writers
+//! wait until no slot holds a pointer before handing it back to the caller. Slots are
+//! allocated on demand, reused after they are released, and never freed.
+
+use core::{
+    ptr::null_mut,
+    sync::atomic::{AtomicBool, AtomicPtr, Ordering},
+};
+
+use alloc::boxed::Box;
+
+/// A slot which protects a single pointer from being reclaimed
+pub(super) struct Slot {
+    ptr: AtomicPtr<()>,
+    in_use: AtomicBool,
+    next: *const Slot,
+}
+
+// SAFETY: `next` is only written before the slot is published, and slots are never freed
+unsafe impl Sync for Slot {}
+
+/// The list of all slots which were ever allocated
+static SLOTS: AtomicPtr<Slot> = AtomicPtr::new(null_mut());
+
+fn slots() -> impl Iterator<Item = &'static Slot> {
+    let mut current = SLOTS.load(Ordering::SeqCst);
+
+    core::iter::from_fn(move || {
+        // SAFETY: slots are never freed, and are initialized before they are published
+        let slot = unsafe { current.as_ref()? };
+        current = slot.next.cast_mut();
+        Some(slot)
+    })
+}
+
+/// Get an unused slot, or allocate a new one if they are all in use
+pub(super) fn acquire() -> &'static Slot {
+    for slot in slots() {
+        if !slot.in_use.load(Ordering::Relaxed)
+            && slot
+                .in_use
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+        {
+            return slot;
+        }
+    }
+
+    let slot = Box::leak(Box::new(Slot {
+        ptr: AtomicPtr::new(null_mut()),
+        in_use: AtomicBool::new(true),
+        next: null_mut(),
+    }));
+
+    let mut head = SLOTS.load(Ordering::SeqCst);
+
+    loop {
+        slot.next = head;
+
+        match SLOTS.compare_exchange_weak(head, slot, Ordering::SeqCst, Ordering::SeqCst) {
+            Ok(_) => return slot,
+            Err(current) => head = current,
+        }
+    }
+}
+
+impl Slot {
+    /// Publish `ptr`, it is only protected once the caller validates that it is still reachable
+    pub(super) fn protect(&self, ptr: *mut ()) {
+        self.ptr.store(ptr, Ordering::SeqCst);
+    }
+
+    /// Stop protecting the pointer and allow the slot to be reused
+    pub(super) fn release(&self) {
+        self.ptr.store(null_mut(), Ordering::Release);
+        self.in_use.store(false, Ordering::Release);
+    }
+}
+
+/// Wait until no slot protects `ptr`
+///
+/// `ptr` must already be unreachable for new readers
+pub(super) fn wait_unprotected(ptr: *mut ()) {
+    for slot in slots() {
+        while slot.ptr.load(Ordering::SeqCst) == ptr {
+            core::hint::spin_loop();
+        }
+    }
+}
@@ -2,15 +2,31 @@
 
 use core::{
     alloc::Layout,
+    ffi::c_void,
+    fmt,
+    hash::{Hash, Hasher},
     marker::PhantomData,
     mem::ManuallyDrop,
     ops::{Deref, DerefMut},
+    pin::Pin,
     ptr::NonNull,
 };
 
-use init::{layout_provider::HasLayoutProvider, Ctor};
+use alloc::boxed::Box;
+use init::{
+    boxed::TryBoxedError,
+    ctor::MoveCtor,
+    layout_provider::HasLayoutProvider,
+    str::Formatted,
+    try_ctor::{of_ctor, OfCtor},
+    try_pin_ctor::{of_pin_ctor, OfPinCtor},
+    Ctor, Init, PinCtor, TryCtor, TryPinCtor,
+};
 
-use crate::ptr::{Metadata, PushHeader, RawThinPtr, WithHeader};
+use crate::{
+    ptr::{Metadata, PushHeader, RawThinPtr, WithHeader},
+    reference::{ThinMut, ThinRef},
+};
 
 /// A type that's like a `Box` mut guaranteed to be the same representation as a `*mut ()`
 #[repr(transparent)]
@@ -19,6 +35,11 @@ pub struct ThinBox<T: ?Sized> {
     ty: PhantomData<T>,
 }
 
+// SAFETY: `ThinBox` owns it's value like a `Box<T>`
+unsafe impl<T: ?Sized + Send> Send for ThinBox<T> {}
+// SAFETY: `ThinBox` owns it's value like a `Box<T>`
+unsafe impl<T: ?Sized + Sync> Sync for ThinBox<T> {}
+
 struct RawThinBox {
     ptr: *mut (),
     layout: Layout,
@@ -52,9 +73,53 @@ impl<T: ?Sized> ThinBox<T> {
     where
         T: Ctor<Args> + HasLayoutProvider<Args>,
     {
-        let bx = init::boxed::boxed::<WithHeader<T>, _>(PushHeader(args));
+        match Self::try_new::<OfCtor<Args>>(of_ctor(args)) {
+            Ok(bx) => bx,
+            Err(err) => err.handle(),
+        }
+    }
 
-        let bx = alloc::boxed::Box::into_raw(bx);
+    /// Try to construct a new ThinBox
+    pub fn try_new<Args>(args: Args) -> Result<Self, TryBoxedError<T::Error>>
+    where
+        T: TryCtor<Args> + HasLayoutProvider<Args>,
+    {
+        let bx = init::boxed::try_boxed::<WithHeader<T>, _>(PushHeader(args))?;
+
+        // SAFETY: the box holds an initialized `WithHeader<T>`
+        Ok(unsafe { Self::from_box(bx) })
+    }
+
+    /// Construct a new pinned ThinBox
+    pub fn pin<Args>(args: Args) -> Pin<Self>
+    where
+        T: PinCtor<Args> + HasLayoutProvider<Args>,
+    {
+        match Self::try_pin::<OfPinCtor<Args>>(of_pin_ctor(args)) {
+            Ok(bx) => bx,
+            Err(err) => err.handle(),
+        }
+    }
+
+    /// Try to construct a new pinned ThinBox
+    pub fn try_pin<Args>(args: Args) -> Result<Pin<Self>, TryBoxedError<T::Error>>
+    where
+        T: TryPinCtor<Args> + HasLayoutProvider<Args>,
+    {
+        let bx = init::pin_boxed::try_pin_boxed::<WithHeader<T>, _>(PushHeader(args))?;
+
+        // SAFETY: the value is never moved out of the `ThinBox`, since it's pinned again right away
+        let bx = unsafe { Pin::into_inner_unchecked(bx) };
+
+        // SAFETY: the box holds an initialized `WithHeader<T>`, which was pinned
+        Ok(unsafe { Pin::new_unchecked(Self::from_box(bx)) })
+    }
+
+    /// # Safety
+    ///
+    /// The value in the box must be initialized
+    unsafe fn from_box(bx: Box<WithHeader<T>>) -> Self {
+        let bx = Box::into_raw(bx);
 
         Self {
             // SAFETY: This pointer came from a box, which is non-null
@@ -76,6 +141,128 @@ impl<T> ThinBox<[T]> {
     }
 }
 
+impl ThinBox<str> {
+    /// Construct a new string from the formatted arguments, without any intermediate allocations
+    pub fn from_fmt(args: fmt::Arguments<'_>) -> Self {
+        Self::new(Formatted::new(args))
+    }
+}
+
+impl From<&str> for ThinBox<str> {
+    fn from(s: &str) -> Self {
+        Self::new(s)
+    }
+}
+
+impl From<alloc::string::String> for ThinBox<str> {
+    fn from(s: alloc::string::String) -> Self {
+        Self::new(s.as_str())
+    }
+}
+
+impl From<ThinBox<str>> for alloc::string::String {
+    fn from(s: ThinBox<str>) -> Self {
+        alloc::string::String::from(&*s)
+    }
+}
+
+impl<T: ?Sized + MoveCtor> ThinBox<T> {
+    /// Move the value into a `Box<T>`
+    ///
+    /// If `T::IS_MOVE_TRIVIAL` holds and the alignments match, then the
+    /// allocation is reused, otherwise the value is moved with [`MoveCtor`]
+    pub fn into_box(self) -> Box<T> {
+        let this = ManuallyDrop::new(self);
+
+        // SAFETY: the pointer is valid, allocated, and initialized, and `this` is never used again
+        unsafe {
+            let ptr = this.ptr.as_mut_with_header_ptr();
+            let value = core::ptr::addr_of_mut!((*ptr).value);
+            let layout = Layout::for_value_raw(ptr);
+            let value_layout = Layout::for_value_raw(value);
+
+            if value_layout.size() != 0
+                && value_layout.align() == layout.align()
+                && T::IS_MOVE_TRIVIAL.get()
+            {
+                let raw = ptr.cast::<u8>();
+                raw.copy_from(value.cast::<u8>(), value_layout.size());
+
+                let raw = alloc::alloc::realloc(raw, layout, value_layout.size());
+                if raw.is_null() {
+                    alloc::alloc::handle_alloc_error(value_layout)
+                }
+
+                let metadata = core::ptr::metadata(value);
+                return Box::from_raw(core::ptr::from_raw_parts_mut(raw.cast::<()>(), metadata));
+            }
+
+            let _alloc = RawThinBox {
+                ptr: ptr.cast(),
+                layout,
+            };
+
+            init::boxed::boxed::<T, _>(Init::from_raw(value))
+        }
+    }
+}
+
+impl<T: ?Sized + MoveCtor> From<Box<T>> for ThinBox<T> {
+    /// Move the value out of the box
+    ///
+    /// If `T::IS_MOVE_TRIVIAL` holds and the alignments match, then the
+    /// allocation is reused, otherwise the value is moved with [`MoveCtor`]
+    fn from(bx: Box<T>) -> Self {
+        let layout = Layout::for_value(&*bx);
+        let value = Box::into_raw(bx);
+
+        if layout.size() != 0 && T::IS_MOVE_TRIVIAL.get() {
+            if let Ok((new_layout, offset)) = Layout::new::<Metadata<T>>().extend(layout) {
+                let new_layout = new_layout.pad_to_align();
+
+                if new_layout.align() == layout.align() {
+                    // SAFETY: the box was allocated with `layout` by the global allocator, and the
+                    // value is trivially movable, so it can be moved with a memcpy
+                    unsafe {
+                        let raw = alloc::alloc::realloc(value.cast(), layout, new_layout.size());
+                        if raw.is_null() {
+                            alloc::alloc::handle_alloc_error(new_layout)
+                        }
+
+                        let metadata = core::ptr::metadata(value);
+                        raw.add(offset).copy_from(raw, layout.size());
+                        raw.cast::<Metadata<T>>().write(metadata);
+
+                        let ptr = core::ptr::from_raw_parts_mut(raw.cast::<()>(), metadata);
+                        return Self::from_raw(RawThinPtr::from_raw(NonNull::new_unchecked(ptr)));
+                    }
+                }
+            }
+        }
+
+        // free the box's allocation once the value was moved out, boxes of zero-sized values don't allocate
+        let _alloc = (layout.size() != 0).then_some(RawThinBox {
+            ptr: value.cast(),
+            layout,
+        });
+
+        // SAFETY: the value is owned by this function, and won't be dropped by the box
+        Self::new(unsafe { Init::from_raw(value) })
+    }
+}
+
+impl<T: MoveCtor> From<ThinBox<[T]>> for Box<[T]> {
+    fn from(bx: ThinBox<[T]>) -> Self {
+        bx.into_box()
+    }
+}
+
+impl From<ThinBox<str>> for Box<str> {
+    fn from(bx: ThinBox<str>) -> Self {
+        bx.into_box()
+    }
+}
+
 impl<T: ?Sized> ThinBox<T> {
     /// Get the length of the slice
     pub fn as_ptr(&self) -> *const T {
@@ -94,6 +281,48 @@ impl<T: ?Sized> ThinBox<T> {
         ManuallyDrop::new(self).ptr
     }
 
+    /// Create a ThinBox from a raw pointer
+    ///
+    /// # Safety
+    ///
+    /// The pointer must have come from [`ThinBox::into_raw`], and must not be used after this call
+    pub unsafe fn from_raw(ptr: RawThinPtr<T>) -> Self {
+        Self {
+            ptr,
+            ty: PhantomData,
+        }
+    }
+
+    /// Borrow as a thin reference
+    pub fn as_thin_ref(&self) -> ThinRef<'_, T> {
+        // SAFETY: the pointer is valid, allocated, and initialized, and is borrowed from `self`
+        unsafe { ThinRef::from_raw(self.ptr) }
+    }
+
+    /// Borrow as a unique thin reference
+    pub fn as_thin_mut(&mut self) -> ThinMut<'_, T> {
+        // SAFETY: the pointer is valid, allocated, and initialized, and is uniquely borrowed from `self`
+        unsafe { ThinMut::from_raw(self.ptr) }
+    }
+
+    /// Convert the box into a type-erased pointer, which can be passed through FFI as a `void*`
+    ///
+    /// The pointer must eventually be passed back to [`ThinBox::from_raw_erased`] to free it
+    pub fn into_raw_erased(self) -> *mut c_void {
+        Self::into_raw(self).as_erased_mut_ptr().cast()
+    }
+
+    /// Create a ThinBox from a type-erased pointer
+    ///
+    /// # Safety
+    ///
+    /// The pointer must have come from [`ThinBox::into_raw_erased`] with the same `T`,
+    /// and must not be used after this call
+    pub unsafe fn from_raw_erased(ptr: *mut c_void) -> Self {
+        // SAFETY: guaranteed by caller
+        unsafe { Self::from_raw(RawThinPtr::from_erased(NonNull::new_unchecked(ptr.cast()))) }
+    }
+
     /// Get the length of the slice
     pub fn metadata(&self) -> Metadata<T> {
         // SAFETY: This pointer is valid, allocated, and initialized
@@ -117,6 +346,44 @@ impl<T: ?Sized> DerefMut for ThinBox<T> {
     }
 }
 
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ThinBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for ThinBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized + Hash> Hash for ThinBox<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        T::hash(self, state)
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for ThinBox<T> {
+    fn eq(&self, other: &Self) -> bool {
+        T::eq(self, other)
+    }
+}
+
+impl<T: ?Sized + Eq> Eq for ThinBox<T> {}
+
+impl<T: ?Sized + PartialOrd> PartialOrd for ThinBox<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        T::partial_cmp(self, other)
+    }
+}
+
+impl<T: ?Sized + Ord> Ord for ThinBox<T> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        T::cmp(self, other)
+    }
+}
+
 #[test]
 fn test_u8() {
     let bx = ThinBox::<u8>::new(());
@@ -134,3 +401,94 @@ fn test_slice_nonzero() {
     let bx = ThinBox::<[u8]>::new(init::slice::ctor::CopyArgsLen(10, 100));
     assert_eq!(*bx, [100; 10]);
 }
+
+#[test]
+fn test_str() {
+    let bx = ThinBox::<str>::from("hello");
+    assert_eq!(&*bx, "hello");
+    assert_eq!(bx.metadata(), 5);
+
+    let bx = ThinBox::<str>::from_fmt(format_args!("{}-{}", "hello", 10));
+    assert_eq!(&*bx, "hello-10");
+    assert_eq!(alloc::string::String::from(bx), "hello-10");
+
+    assert!(ThinBox::<str>::from("a") < ThinBox::<str>::from("b"));
+}
+
+#[test]
+fn test_dyn() {
+    use core::fmt::Display;
+    use init::unsize::Coerce;
+
+    let bx = ThinBox::<dyn Display>::new(Coerce::<u32, _>::new(42u32));
+    assert_eq!(alloc::format!("{bx}"), "42");
+    assert_eq!(
+        core::mem::size_of::<ThinBox<dyn Display>>(),
+        core::mem::size_of::<*mut ()>()
+    );
+
+    let bx = ThinBox::<[u8]>::new(Coerce::<[u8; 4], _>::new(init::slice::ctor::CopyArgs(1u8)));
+    assert_eq!(*bx, [1; 4]);
+}
+
+#[test]
+fn test_try_new() {
+    use init::slice::try_ctor::{IterInitError, IterLenInit};
+
+    let bx = ThinBox::<[u8]>::try_new(IterLenInit::new([1, 2, 3].into_iter().map(of_ctor)));
+    assert_eq!(*bx.ok().unwrap(), [1, 2, 3]);
+
+    let bx = ThinBox::<[u8]>::try_new(IterLenInit(3, [1, 2].into_iter().map(of_ctor)));
+    assert!(matches!(
+        bx,
+        Err(TryBoxedError::InitError(IterInitError::NotEnoughItems))
+    ));
+}
+
+#[test]
+fn test_pin() {
+    use core::marker::PhantomPinned;
+
+    struct Immovable {
+        this: *const Immovable,
+        _pin: PhantomPinned,
+    }
+
+    impl PinCtor for Immovable {
+        fn pin_init(uninit: init::Uninit<'_, Self>, (): ()) -> init::PinInit<'_, Self> {
+            let this = uninit.as_ptr();
+            uninit
+                .write(Immovable {
+                    this,
+                    _pin: PhantomPinned,
+                })
+                .pin()
+        }
+    }
+
+    impl HasLayoutProvider for Immovable {
+        type LayoutProvider = init::layout_provider::SizedLayoutProvider;
+    }
+
+    let bx = ThinBox::<Immovable>::pin(());
+    assert_eq!(bx.this, &*bx as *const Immovable);
+}
+
+#[test]
+fn test_box_conversions() {
+    use alloc::vec;
+
+    let bx = ThinBox::<[u64]>::from(vec![1, 2, 3].into_boxed_slice());
+    assert_eq!(*bx, [1, 2, 3]);
+    assert_eq!(*Box::<[u64]>::from(bx), [1, 2, 3]);
+
+    let bx = ThinBox::<[u8]>::from(vec![1, 2, 3].into_boxed_slice());
+    assert_eq!(*bx, [1, 2, 3]);
+    assert_eq!(*Box::<[u8]>::from(bx), [1, 2, 3]);
+
+    let bx = ThinBox::<str>::from(Box::<str>::from("hello"));
+    assert_eq!(*Box::<str>::from(bx), *"hello");
+
+    let bx = ThinBox::<u32>::from(Box::new(10));
+    assert_eq!(*bx.into_box(), 10);
+}
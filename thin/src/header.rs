@@ -0,0 +1,510 @@
+//! Thin allocations which carry a user-defined header next to the pointer metadata
+
+use core::{
+    alloc::Layout,
+    marker::PhantomData,
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+
+use alloc::alloc::handle_alloc_error;
+use init::{
+    ctor::MoveCtor,
+    layout_provider::{HasLayoutProvider, LayoutProvider},
+    Ctor, Init,
+};
+
+use crate::{
+    boxed::ThinBox,
+    ptr::{PushHeader, RawThinPtr, WithHeader},
+    vec::{new_capacity, VecData, WithCapacity},
+};
+
+/// A user-defined header followed by the value
+#[repr(C)]
+pub struct Headed<H, T: ?Sized> {
+    /// The user-defined header
+    pub header: H,
+    /// The value
+    pub value: T,
+}
+
+/// A constructor for `Headed`, which constructs the header from `HArgs` and the value from `Args`
+pub struct NewHeaded<HArgs, Args>(pub HArgs, pub Args);
+
+/// The layout provider for `Headed`
+pub struct HeadedLayoutProvider;
+
+// SAFETY: the layout given by layout_of matches the algorithm used to calculate the layout of
+// repr(C) structs
+unsafe impl<H, T: ?Sized + HasLayoutProvider<Args>, HArgs, Args>
+    LayoutProvider<Headed<H, T>, NewHeaded<HArgs, Args>> for HeadedLayoutProvider
+{
+    fn layout_of(args: &NewHeaded<HArgs, Args>) -> Option<Layout> {
+        let value_layout = init::layout_provider::layout_of::<T, Args>(&args.1)?;
+        let (layout, _) = Layout::new::<H>().extend(value_layout).ok()?;
+        Some(layout.pad_to_align())
+    }
+
+    unsafe fn cast(ptr: NonNull<u8>, args: &NewHeaded<HArgs, Args>) -> NonNull<Headed<H, T>> {
+        // SAFETY: `Self::layout_of` only returns a layout if `T::layout_of` returns Some
+        let ptr = unsafe { init::layout_provider::cast::<T, Args>(ptr, &args.1) };
+        // SAFETY: `ptr` is non-null
+        unsafe { NonNull::new_unchecked(ptr.as_ptr() as *mut Headed<H, T>) }
+    }
+}
+
+impl<H, T: ?Sized + HasLayoutProvider<Args>, HArgs, Args> HasLayoutProvider<NewHeaded<HArgs, Args>>
+    for Headed<H, T>
+{
+    type LayoutProvider = HeadedLayoutProvider;
+}
+
+impl<H: Ctor<HArgs>, T: ?Sized + Ctor<Args>, HArgs, Args> Ctor<NewHeaded<HArgs, Args>>
+    for Headed<H, T>
+{
+    #[inline]
+    fn init(
+        uninit: init::Uninit<'_, Self>,
+        NewHeaded(hargs, args): NewHeaded<HArgs, Args>,
+    ) -> init::Init<'_, Self> {
+        init::init_struct! {
+            uninit => Self {
+                header: hargs,
+                value: args,
+            }
+        }
+    }
+}
+
+/// A [`ThinBox`] which also stores a user-defined header in the same allocation
+pub struct ThinBoxWithHeader<H, T: ?Sized> {
+    bx: ThinBox<Headed<H, T>>,
+}
+
+impl<H, T: ?Sized> ThinBoxWithHeader<H, T> {
+    /// Construct a new ThinBoxWithHeader
+    pub fn new<HArgs, Args>(hargs: HArgs, args: Args) -> Self
+    where
+        H: Ctor<HArgs>,
+        T: Ctor<Args> + HasLayoutProvider<Args>,
+    {
+        Self {
+            bx: ThinBox::new(NewHeaded(hargs, args)),
+        }
+    }
+
+    /// Get a reference to the header
+    pub fn header(&self) -> &H {
+        &self.bx.header
+    }
+
+    /// Get a mutable reference to the header
+    pub fn header_mut(&mut self) -> &mut H {
+        &mut self.bx.header
+    }
+}
+
+impl<H, T: ?Sized> Deref for ThinBoxWithHeader<H, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.bx.value
+    }
+}
+
+impl<H, T: ?Sized> DerefMut for ThinBoxWithHeader<H, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.bx.value
+    }
+}
+
+type HeaderVecData<H, T> = Headed<H, VecData<T>>;
+
+/// A thin vector which stores a user-defined header, the length and capacity on the heap
+///
+/// Unlike [`ThinVec`](crate::vec::ThinVec), this always allocates since the header must live somewhere
+pub struct HeaderVec<H, T> {
+    ptr: RawThinPtr<HeaderVecData<H, T>>,
+    _drop: PhantomData<(H, T)>,
+}
+
+impl<H, T> Drop for HeaderVec<H, T> {
+    fn drop(&mut self) {
+        let ptr = self.as_mut_headed_ptr();
+
+        // SAFETY: the pointer is valid, allocated, and the header and the first `len` elements are initialized
+        unsafe {
+            let layout = Layout::for_value_raw(self.ptr.as_mut_with_header_ptr());
+            let _alloc = RawAlloc {
+                ptr: self.ptr.as_erased_mut_ptr(),
+                layout,
+            };
+
+            let elements = core::ptr::slice_from_raw_parts_mut(self.as_mut_ptr(), self.len());
+            let _header = DropHeader(core::ptr::addr_of_mut!((*ptr).header));
+            elements.drop_in_place();
+        }
+    }
+}
+
+/// Frees the allocation without dropping anything
+struct RawAlloc {
+    ptr: *mut (),
+    layout: Layout,
+}
+
+impl Drop for RawAlloc {
+    fn drop(&mut self) {
+        // SAFETY: the pointer is valid and allocated by the global allocator
+        unsafe { alloc::alloc::dealloc(self.ptr.cast(), self.layout) }
+    }
+}
+
+/// Drops the header, even if dropping the elements panics
+struct DropHeader<H>(*mut H);
+
+impl<H> Drop for DropHeader<H> {
+    fn drop(&mut self) {
+        // SAFETY: the header is initialized, and is only dropped once
+        unsafe { self.0.drop_in_place() }
+    }
+}
+
+impl<H, T> HeaderVec<H, T> {
+    /// Create a new vector with the given header
+    pub fn new<HArgs>(hargs: HArgs) -> Self
+    where
+        H: Ctor<HArgs>,
+    {
+        Self::with_capacity(hargs, 0)
+    }
+
+    /// Create a new vector with the given header and capacity
+    pub fn with_capacity<HArgs>(hargs: HArgs, capacity: usize) -> Self
+    where
+        H: Ctor<HArgs>,
+    {
+        let bx = ThinBox::<HeaderVecData<H, T>>::new(NewHeaded(hargs, WithCapacity(capacity)));
+
+        Self {
+            ptr: ThinBox::into_raw(bx),
+            _drop: PhantomData,
+        }
+    }
+
+    fn as_headed_ptr(&self) -> *const HeaderVecData<H, T> {
+        // SAFETY: the pointer is valid and allocated
+        unsafe { self.ptr.as_ptr() }
+    }
+
+    fn as_mut_headed_ptr(&mut self) -> *mut HeaderVecData<H, T> {
+        // SAFETY: the pointer is valid and allocated
+        unsafe { self.ptr.as_mut_ptr() }
+    }
+
+    /// Get a reference to the header
+    pub fn header(&self) -> &H {
+        // SAFETY: the header is always initialized
+        unsafe { &(*self.as_headed_ptr()).header }
+    }
+
+    /// Get a mutable reference to the header
+    pub fn header_mut(&mut self) -> &mut H {
+        // SAFETY: the header is always initialized
+        unsafe { &mut (*self.as_mut_headed_ptr()).header }
+    }
+
+    /// The number of elements the vector can hold without reallocating
+    pub fn capacity(&self) -> usize {
+        if core::mem::size_of::<T>() == 0 {
+            usize::MAX
+        } else {
+            // SAFETY: the pointer is valid and allocated
+            unsafe { self.ptr.metadata() }
+        }
+    }
+
+    /// The number of elements in the vector
+    pub fn len(&self) -> usize {
+        // SAFETY: the pointer is valid and allocated
+        unsafe { (*self.as_headed_ptr()).value.len }
+    }
+
+    /// Checks if the vector has no elements
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get a raw pointer to the first element
+    pub fn as_ptr(&self) -> *const T {
+        let ptr = self.as_headed_ptr();
+        // SAFETY: the pointer is valid and allocated
+        unsafe { core::ptr::addr_of!((*ptr).value.data).cast() }
+    }
+
+    /// Get a raw pointer to the first element
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        let ptr = self.as_mut_headed_ptr();
+        // SAFETY: the pointer is valid and allocated
+        unsafe { core::ptr::addr_of_mut!((*ptr).value.data).cast() }
+    }
+
+    /// Get the elements of the vector
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: the first `len` elements are initialized
+        unsafe { core::slice::from_raw_parts(self.as_ptr(), self.len()) }
+    }
+
+    /// Get the elements of the vector
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        let len = self.len();
+        // SAFETY: the first `len` elements are initialized
+        unsafe { core::slice::from_raw_parts_mut(self.as_mut_ptr(), len) }
+    }
+
+    /// # Safety
+    ///
+    /// `len <= capacity` and the first `len` elements must be initialized
+    unsafe fn set_len(&mut self, len: usize) {
+        let ptr = self.as_mut_headed_ptr();
+        // SAFETY: guaranteed by caller
+        unsafe { (*ptr).value.len = len }
+    }
+
+    /// Construct and push a value in place
+    ///
+    /// # Safety
+    ///
+    /// The length must not be equal to the capacity
+    pub unsafe fn emplace_unchecked<Args>(&mut self, args: Args)
+    where
+        T: Ctor<Args>,
+    {
+        let len = self.len();
+
+        // SAFETY: the caller guarantees that there is space for another element
+        let uninit = unsafe { init::Uninit::from_raw(self.as_mut_ptr().add(len)) };
+        let init = uninit.init(args);
+
+        // the vector will take ownership of the value
+        init.take_ownership();
+
+        // SAFETY: the element at `len` was just initialized
+        unsafe { self.set_len(len + 1) }
+    }
+
+    /// Remove the last element from the vector
+    pub fn pop(&mut self) -> Option<Init<'_, T>> {
+        let len = self.len().checked_sub(1)?;
+
+        // SAFETY: the element at `len` is initialized, and the vector no longer owns it
+        unsafe {
+            self.set_len(len);
+            Some(Init::from_raw(self.as_mut_ptr().add(len)))
+        }
+    }
+}
+
+impl<H, T: MoveCtor> HeaderVec<H, T> {
+    /// Reserve space for at least `additional` more elements
+    pub fn reserve(&mut self, additional: usize) {
+        if self.capacity() - self.len() < additional {
+            self.reserve_inner(additional)
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn reserve_inner(&mut self, additional: usize) {
+        assert!(
+            core::mem::size_of::<T>() != 0,
+            "Cannot reserve more than usize::MAX elements for Zero Sized Types"
+        );
+
+        let capacity = self.capacity();
+        let new_capacity =
+            new_capacity(capacity, additional).expect("Could not calculate new capacity");
+        let layout_of = |capacity| {
+            init::layout_provider::layout_of::<WithHeader<HeaderVecData<H, T>>, _>(&PushHeader(
+                NewHeaded((), WithCapacity(capacity)),
+            ))
+        };
+        let new_layout = layout_of(new_capacity).expect("Could not calculate new layout");
+        // SAFETY: the current layout was already calculated when allocating
+        let layout = unsafe { layout_of(capacity).unwrap_unchecked() };
+
+        let old = self.ptr.as_erased_mut_ptr().cast::<u8>();
+
+        let ptr = if T::IS_MOVE_TRIVIAL.get() {
+            // SAFETY: the header can be moved with a memcpy since it is never pinned,
+            // and the elements are trivially movable
+            unsafe { alloc::alloc::realloc(old, layout, new_layout.size()) }
+        } else {
+            // SAFETY: the layout has a non-zero size, since it holds the capacity
+            unsafe { alloc::alloc::alloc(new_layout) }
+        };
+
+        let Some(ptr) = NonNull::new(ptr) else {
+            handle_alloc_error(new_layout);
+        };
+
+        // SAFETY: the layout was calculated from these arguments
+        let ptr = unsafe {
+            init::layout_provider::cast::<WithHeader<HeaderVecData<H, T>>, _>(
+                ptr,
+                &PushHeader(NewHeaded((), WithCapacity(new_capacity))),
+            )
+        };
+
+        if !T::IS_MOVE_TRIVIAL.get() {
+            // SAFETY: `ptr` was just allocated with space for the header, and at least `len` elements
+            unsafe { self.move_into(ptr.as_ptr()) }
+            let _alloc = RawAlloc {
+                ptr: old.cast(),
+                layout,
+            };
+        }
+
+        // SAFETY: the new allocation holds the header and all elements, and needs
+        // it's capacity updated
+        unsafe { (*ptr.as_ptr()).metadata = new_capacity }
+        self.ptr = RawThinPtr::from_raw(ptr);
+    }
+
+    /// Move the header and all elements into `dest`, leaving `self` empty
+    ///
+    /// # Safety
+    ///
+    /// `dest` must have space for all elements
+    unsafe fn move_into(&mut self, dest: *mut WithHeader<HeaderVecData<H, T>>) {
+        let len = self.len();
+        let source = self.as_mut_ptr();
+
+        // SAFETY: the header is never pinned, so it can be moved with a memcpy
+        // the vector still owns the header until it's length is set to zero
+        unsafe {
+            let dest = core::ptr::addr_of_mut!((*dest).value);
+            core::ptr::addr_of_mut!((*dest).header).copy_from_nonoverlapping(self.header(), 1);
+            core::ptr::addr_of_mut!((*dest).value.len).write(0);
+        }
+
+        // if moving an element panics, then all elements are leaked, which is safe
+        // SAFETY: no elements will be accessed through `self` until the length is reset
+        unsafe { self.set_len(0) }
+
+        for i in 0..len {
+            // SAFETY: the element at `i` is initialized and owned by this function,
+            // and `dest` has space for `len` elements
+            unsafe {
+                let item = Init::from_raw(source.add(i));
+                let slot = core::ptr::addr_of_mut!((*dest).value.value.data)
+                    .cast::<MaybeUninit<T>>()
+                    .add(i);
+                init::Uninit::from_raw(slot.cast::<T>())
+                    .init(item)
+                    .take_ownership();
+            }
+        }
+
+        // SAFETY: all elements were moved into `dest`
+        unsafe { core::ptr::addr_of_mut!((*dest).value.value.len).write(len) }
+    }
+
+    /// Construct and push a value in place
+    pub fn emplace<Args>(&mut self, args: Args)
+    where
+        T: Ctor<Args>,
+    {
+        self.reserve(1);
+
+        // SAFETY: just reserved enough space
+        unsafe { self.emplace_unchecked(args) }
+    }
+}
+
+impl<H, T> Deref for HeaderVec<H, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<H, T> DerefMut for HeaderVec<H, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_slice()
+    }
+}
+
+#[test]
+fn test_box_with_header() {
+    let mut bx = ThinBoxWithHeader::<u32, [u8]>::new(7, init::slice::ctor::CopyArgsLen(3, 1));
+    assert_eq!(core::mem::size_of_val(&bx), core::mem::size_of::<*mut ()>());
+    assert_eq!(*bx.header(), 7);
+    assert_eq!(*bx, [1, 1, 1]);
+
+    *bx.header_mut() += 1;
+    bx[0] = 2;
+    assert_eq!(*bx.header(), 8);
+    assert_eq!(*bx, [2, 1, 1]);
+}
+
+#[test]
+fn test_header_vec_zst() {
+    let mut v = HeaderVec::<u8, ()>::new(7);
+    assert_eq!(v.capacity(), usize::MAX);
+
+    for _ in 0..10 {
+        v.emplace(());
+    }
+
+    assert_eq!(*v.header(), 7);
+    assert_eq!(v.len(), 10);
+    assert!(v.pop().is_some());
+    assert_eq!(v.len(), 9);
+}
+
+#[test]
+fn test_header_vec() {
+    use alloc::rc::Rc;
+
+    let mut v = HeaderVec::<u64, u32>::new(42);
+    assert!(v.is_empty());
+
+    for i in 0..20 {
+        v.emplace(i);
+    }
+
+    assert_eq!(*v.header(), 42);
+    assert_eq!(v.len(), 20);
+    assert!(v.iter().copied().eq(0..20));
+    assert_eq!(v.pop().map(Init::into_inner), Some(19));
+
+    struct Counted(Rc<()>);
+
+    impl MoveCtor for Counted {
+        fn move_ctor<'this>(uninit: init::Uninit<'this, Self>, p: Init<Self>) -> Init<'this, Self> {
+            uninit.write(p.into_inner())
+        }
+    }
+
+    impl Ctor<&Rc<()>> for Counted {
+        fn init<'a>(uninit: init::Uninit<'a, Self>, rc: &Rc<()>) -> Init<'a, Self> {
+            uninit.write(Counted(rc.clone()))
+        }
+    }
+
+    let counter = Rc::new(());
+    let mut v = HeaderVec::<Rc<()>, Counted>::new(init::ctor(|u| u.write(counter.clone())));
+
+    for _ in 0..10 {
+        v.emplace(&counter);
+    }
+
+    assert!(v.iter().all(|c| Rc::ptr_eq(&c.0, &counter)));
+    assert_eq!(Rc::strong_count(&counter), 12);
+    drop(v);
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
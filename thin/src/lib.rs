@@ -5,21 +5,40 @@
     unsafe_op_in_unsafe_fn,
     // clippy::undocumented_unsafe_blocks
 )]
-#![feature(ptr_metadata, slice_range)]
+#![feature(layout_for_ptr, ptr_metadata, slice_range)]
 
 //! A thin pointer library which uses `init` for safe initialization
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
-#[cfg(feature = "std")]
+#[cfg(any(test, feature = "std"))]
 extern crate std;
 
 pub mod boxed;
+#[cfg(feature = "alloc")]
+pub mod header;
 pub mod ptr;
+pub mod reference;
 
+#[cfg(feature = "alloc")]
+pub mod arc;
+#[cfg(feature = "alloc")]
+pub mod atomic;
 #[cfg(feature = "alloc")]
 pub mod pin_vec;
 #[cfg(feature = "alloc")]
+pub mod rc;
+#[cfg(feature = "alloc")]
+pub mod seg_vec;
+#[cfg(feature = "alloc")]
+pub mod small_vec;
+#[cfg(feature = "alloc")]
+pub mod string;
+#[cfg(feature = "alloc")]
+pub mod tagged;
+#[cfg(feature = "alloc")]
 pub mod vec;
 
 mod core_ext;
+#[cfg(feature = "alloc")]
+mod rc_box;
@@ -3,6 +3,9 @@
 //! and guarantees that the values will be dropped before the underling memory is freed
 #![forbid(clippy::undocumented_unsafe_blocks)]
 
+mod iter;
+
+use core::ops::RangeBounds;
 use core::{alloc::Layout, marker::PhantomData, mem::MaybeUninit, pin::Pin, ptr::NonNull};
 
 use alloc::alloc::handle_alloc_error;
@@ -292,6 +295,32 @@ impl<T> ThinPinVec<T> {
         //  SAFETY: The vector isn't empty
         Some(unsafe { self.pop_unchecked() })
     }
+
+    /// Convert the vector into a consuming iterator
+    ///
+    /// See [`IntoIterator`] for `Unpin` types
+    pub fn into_pin_iter(mut self) -> iter::IntoIter<T> {
+        let len = self.len();
+
+        // SAFETY: the iterator takes ownership of all elements, and drops
+        // any elements that aren't yielded
+        unsafe { self.set_len(0) }
+
+        iter::IntoIter {
+            vec: self,
+            start: 0,
+            end: len,
+        }
+    }
+}
+
+impl<T: Unpin> IntoIterator for ThinPinVec<T> {
+    type Item = T;
+    type IntoIter = iter::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_pin_iter()
+    }
 }
 
 impl<T: PinMoveCtor> ThinPinVec<T> {
@@ -375,6 +404,78 @@ impl<T: PinMoveCtor> ThinPinVec<T> {
         *self = new_vec
     }
 
+    /// Move `count` elements starting at `from` to the end of the vector, and push them
+    ///
+    /// # Safety
+    ///
+    /// * `self.len() <= from` and `from + count <= self.capacity()`
+    /// * the elements `self.len()..from` must not be initialized
+    /// * the elements `from..from + count` must be initialized and owned by the vector
+    unsafe fn move_tail(&mut self, from: usize, count: usize) {
+        let len = self.len();
+
+        if len == from || count == 0 {
+            // SAFETY: the elements are already in place
+            unsafe { self.set_len(len + count) }
+            return;
+        }
+
+        let data = self.as_mut_ptr();
+
+        if T::IS_MOVE_TRIVIAL.get() {
+            // SAFETY: the caller guarantees that both ranges are in bounds
+            // and `T::IS_MOVE_TRIVIAL` guarantees that a memmove is a valid move
+            unsafe {
+                data.add(len).copy_from(data.add(from), count);
+                self.set_len(len + count);
+            }
+        } else {
+            // SAFETY: the caller guarantees that these elements are initialized and owned by
+            // the vector. If moving any element panics, the remaining elements will be dropped
+            let tail = unsafe {
+                init::PinInit::from_raw(core::ptr::slice_from_raw_parts_mut(data.add(from), count))
+            };
+
+            for item in tail {
+                // SAFETY: each element is moved to a vacant slot before `from + count`,
+                // which is in capacity, and all slots up to the moved element are vacant
+                unsafe { self.emplace_unchecked(item) }
+            }
+        }
+    }
+
+    /// Remove the elements in `range` from the vector, and yield them in place
+    ///
+    /// Any elements which aren't yielded will be dropped in place when the iterator
+    /// is dropped. Then the tail of the vector will be moved to fill the gap
+    pub fn drain(&mut self, range: impl RangeBounds<usize>) -> iter::Drain<'_, T> {
+        let old_len = self.len();
+        let range = core::slice::range(range, ..old_len);
+
+        // SAFETY: the elements in the range are owned by the drain, and the
+        // tail is moved back into place by the drain
+        unsafe { self.set_len(range.start) }
+
+        // SAFETY: `range` is in bounds of the initialized elements, which are pinned
+        let items = unsafe {
+            init::PinInit::from_raw(core::ptr::slice_from_raw_parts_mut(
+                self.as_mut_ptr().add(range.start),
+                range.end - range.start,
+            ))
+        };
+
+        // the allocation is moved into the drain, so if it's leaked so is the allocation
+        let alloc = core::mem::replace(self, Self::new());
+
+        iter::Drain {
+            ptr: core::mem::ManuallyDrop::new(alloc).ptr,
+            vec: self,
+            iter: items.into_iter(),
+            tail_offset: range.end,
+            tail_len: old_len - range.end,
+        }
+    }
+
     pub fn try_emplace<Args>(&mut self, args: Args) -> Result<(), T::Error>
     where
         T: TryPinCtor<Args>,
@@ -396,6 +497,215 @@ impl<T: PinMoveCtor> ThinPinVec<T> {
             Err(inf) => match inf {},
         }
     }
+
+    /// Move the elements `index..len` up by one, leaving a vacant slot at `index`
+    ///
+    /// The length of the vector is set to `index`, and the returned guard owns the moved elements.
+    /// If moving any element panics, all elements after `index` are dropped in place
+    ///
+    /// # Safety
+    ///
+    /// `index < len` and `len < capacity`
+    unsafe fn shift_up(&mut self, index: usize) -> DropRange<T> {
+        let len = self.len();
+
+        // SAFETY: the elements `index..len` are owned by the guards below
+        unsafe { self.set_len(index) }
+
+        let data = self.as_mut_ptr();
+        let mut moved = DropRange {
+            data,
+            start: len + 1,
+            end: len + 1,
+        };
+
+        if T::IS_MOVE_TRIVIAL.get() {
+            // SAFETY: the caller guarantees that there is space for one more element
+            // and `T::IS_MOVE_TRIVIAL` guarantees that a memmove is a valid move
+            unsafe { data.add(index + 1).copy_from(data.add(index), len - index) }
+            moved.start = index + 1;
+            return moved;
+        }
+
+        let mut unmoved = DropRange {
+            data,
+            start: index,
+            end: len,
+        };
+
+        while unmoved.end > index {
+            let i = unmoved.end - 1;
+            unmoved.end = i;
+
+            // SAFETY: the element at `i` is initialized, and no longer owned by `unmoved`
+            // the slot at `i + 1` is vacant, since it was either past the end of the vector
+            // or it's element was already moved
+            unsafe {
+                let item = init::PinInit::from_raw(data.add(i));
+                init::Uninit::from_raw(data.add(i + 1))
+                    .pin_init(item)
+                    .take_ownership();
+            }
+
+            moved.start = i + 1;
+        }
+
+        moved
+    }
+
+    /// Try to construct a value in place at `index`, shifting all elements after it up by one
+    ///
+    /// If the constructor fails, the elements are moved back into place
+    ///
+    /// # Panics
+    ///
+    /// if `index > len`
+    pub fn try_insert_with<Args>(&mut self, index: usize, args: Args) -> Result<(), T::Error>
+    where
+        T: TryPinCtor<Args>,
+    {
+        let len = self.len();
+        assert!(
+            index <= len,
+            "insertion index (is {index}) should be <= len (is {len})"
+        );
+
+        if self.is_full() {
+            self.reserve_inner(1);
+        }
+
+        if index == len {
+            // SAFETY: We just reserved enough space if there wasn't enough already
+            return unsafe { self.try_emplace_unchecked(args) };
+        }
+
+        // SAFETY: `index < len` and there is space for one more element
+        let tail = unsafe { self.shift_up(index) };
+
+        // SAFETY: `shift_up` left the slot at `index` vacant
+        let uninit = unsafe { init::Uninit::from_raw(self.as_mut_ptr().add(index)) };
+
+        match uninit.try_pin_init(args) {
+            Ok(init) => {
+                // the vector will take ownership of the values
+                init.take_ownership();
+                core::mem::forget(tail);
+                // SAFETY: all elements `0..=len` are initialized
+                unsafe { self.set_len(len + 1) }
+                Ok(())
+            }
+            Err(err) => {
+                core::mem::forget(tail);
+                // SAFETY: the slot at `index` is vacant, and the tail was shifted up by one
+                unsafe { self.move_tail(index + 1, len - index) }
+                Err(err)
+            }
+        }
+    }
+
+    /// Construct a value in place at `index`, shifting all elements after it up by one
+    ///
+    /// # Panics
+    ///
+    /// if `index > len`
+    pub fn insert_with<Args>(&mut self, index: usize, args: Args)
+    where
+        T: PinCtor<Args>,
+    {
+        match self.try_insert_with(index, of_pin_ctor(args)) {
+            Ok(()) => (),
+            Err(inf) => match inf {},
+        }
+    }
+
+    /// Drop the element at `index` in place, shifting all elements after it down by one
+    ///
+    /// # Panics
+    ///
+    /// if `index >= len`
+    pub fn remove(&mut self, index: usize) {
+        let len = self.len();
+        assert!(
+            index < len,
+            "removal index (is {index}) should be < len (is {len})"
+        );
+
+        self.drain(index..=index);
+    }
+
+    /// Only keep the elements for which `f` returns true, and drop the rest in place
+    ///
+    /// The kept elements are moved down to fill the gaps, and keep their relative order.
+    /// If `f` panics, then all elements that weren't visited yet are dropped
+    pub fn retain_pinned<F: FnMut(Pin<&mut T>) -> bool>(&mut self, mut f: F) {
+        let len = self.len();
+
+        // SAFETY: the elements are owned by `rest` until they are kept
+        unsafe { self.set_len(0) }
+
+        let data = self.as_mut_ptr();
+        let mut rest = DropRange {
+            data,
+            start: 0,
+            end: len,
+        };
+        let mut kept = 0;
+
+        while rest.start < len {
+            let i = rest.start;
+
+            // SAFETY: the element at `i` is initialized and pinned
+            let keep = f(unsafe { Pin::new_unchecked(&mut *data.add(i)) });
+
+            rest.start = i + 1;
+
+            if !keep {
+                // SAFETY: the element at `i` is initialized and no longer owned by `rest`
+                unsafe { data.add(i).drop_in_place() }
+                continue;
+            }
+
+            if i != kept {
+                if T::IS_MOVE_TRIVIAL.get() {
+                    // SAFETY: the slot at `kept` is vacant, and `T::IS_MOVE_TRIVIAL`
+                    // guarantees that a memcpy is a valid move
+                    unsafe { data.add(kept).copy_from_nonoverlapping(data.add(i), 1) }
+                } else {
+                    // SAFETY: the element at `i` is initialized and no longer owned by `rest`
+                    // and the slot at `kept` is vacant
+                    unsafe {
+                        let item = init::PinInit::from_raw(data.add(i));
+                        init::Uninit::from_raw(data.add(kept))
+                            .pin_init(item)
+                            .take_ownership();
+                    }
+                }
+            }
+
+            kept += 1;
+
+            // SAFETY: all elements `0..kept` are initialized
+            unsafe { self.set_len(kept) }
+        }
+    }
+}
+
+/// Drops the elements `start..end` in place, used to clean up if a move panics
+struct DropRange<T> {
+    data: *mut T,
+    start: usize,
+    end: usize,
+}
+
+impl<T> Drop for DropRange<T> {
+    fn drop(&mut self) {
+        // SAFETY: the owner of the guard ensures that the elements `start..end`
+        // are initialized and owned by the guard
+        unsafe {
+            core::ptr::slice_from_raw_parts_mut(self.data.add(self.start), self.end - self.start)
+                .drop_in_place()
+        }
+    }
 }
 
 struct WithCapacity(usize);
@@ -540,3 +850,122 @@ fn test_pin_vec_zst() {
 
     vec.emplace(());
 }
+
+#[cfg(test)]
+struct Tracked {
+    this: *const Tracked,
+    value: u32,
+}
+
+#[cfg(test)]
+impl init::PinCtor<u32> for Tracked {
+    fn pin_init(uninit: init::Uninit<'_, Self>, value: u32) -> init::PinInit<'_, Self> {
+        let this = uninit.as_ptr();
+        uninit.write(Tracked { this, value }).pin()
+    }
+}
+
+#[cfg(test)]
+impl PinMoveCtor for Tracked {
+    fn pin_move_ctor<'this>(
+        uninit: init::Uninit<'this, Self>,
+        p: init::PinInit<Self>,
+    ) -> init::PinInit<'this, Self> {
+        assert_eq!(p.get().this, p.as_ptr());
+        uninit.pin_init(p.get().value)
+    }
+}
+
+#[cfg(test)]
+impl Tracked {
+    fn check(vec: &ThinPinVec<Tracked>) -> impl Iterator<Item = u32> + '_ {
+        vec.as_slice().iter().map(|x| {
+            assert_eq!(x.this, x as *const Tracked);
+            x.value
+        })
+    }
+}
+
+#[test]
+fn test_pin_vec_drain() {
+    let mut vec = ThinPinVec::<Tracked>::new();
+
+    vec.drain(..);
+
+    for i in 0..10 {
+        vec.emplace(i);
+    }
+
+    let value = vec.drain(2..5).next_pinned().map(|x| x.get().value);
+    assert_eq!(value, Some(2));
+    assert!(Tracked::check(&vec).eq([0, 1, 5, 6, 7, 8, 9]));
+
+    vec.drain(5..);
+    assert!(Tracked::check(&vec).eq([0, 1, 5, 6, 7]));
+
+    let value = vec.drain(..2).next_back_pinned().map(|x| x.get().value);
+    assert_eq!(value, Some(1));
+    assert!(Tracked::check(&vec).eq([5, 6, 7]));
+
+    core::mem::forget(vec.drain(1..2));
+    assert!(vec.is_empty());
+}
+
+#[test]
+fn test_pin_vec_into_iter() {
+    let mut vec = ThinPinVec::<Tracked>::new();
+
+    for i in 0..10 {
+        vec.emplace(i);
+    }
+
+    let mut iter = vec.into_pin_iter();
+    assert_eq!(iter.next_pinned().map(|x| x.get().value), Some(0));
+    assert_eq!(iter.next_back_pinned().map(|x| x.get().value), Some(9));
+    assert_eq!(iter.len(), 8);
+    drop(iter);
+
+    let mut vec = ThinPinVec::<u8>::new();
+
+    for i in 0..10 {
+        vec.emplace(i);
+    }
+
+    assert!(vec.into_iter().eq(0..10));
+}
+
+#[test]
+fn test_pin_vec_insert_remove() {
+    let mut vec = ThinPinVec::<Tracked>::new();
+
+    vec.insert_with(0, 1);
+    vec.insert_with(0, 0);
+    vec.insert_with(2, 3);
+    vec.insert_with(2, 2);
+    assert!(Tracked::check(&vec).eq([0, 1, 2, 3]));
+
+    for i in 4..10 {
+        vec.insert_with(1, i);
+    }
+    assert!(Tracked::check(&vec).eq([0, 9, 8, 7, 6, 5, 4, 1, 2, 3]));
+
+    let res = vec.try_insert_with(3, init::try_pin_ctor::try_pin_ctor(|_| Err(())));
+    assert_eq!(res, Err(()));
+    assert!(Tracked::check(&vec).eq([0, 9, 8, 7, 6, 5, 4, 1, 2, 3]));
+
+    vec.remove(1);
+    vec.remove(8);
+    assert!(Tracked::check(&vec).eq([0, 8, 7, 6, 5, 4, 1, 2]));
+
+    vec.retain_pinned(|x| x.value % 2 == 0);
+    assert!(Tracked::check(&vec).eq([0, 8, 6, 4, 2]));
+
+    let mut vec = ThinPinVec::<u8>::new();
+
+    for i in 0..10 {
+        vec.insert_with(0, i);
+    }
+    vec.remove(0);
+    vec.retain_pinned(|x| *x % 3 != 0);
+    assert_eq!(vec.as_slice(), [8, 7, 5, 4, 2, 1]);
+}
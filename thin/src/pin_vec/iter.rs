@@ -0,0 +1,169 @@
+use core::marker::PhantomData;
+
+use init::{pin_ctor::PinMoveCtor, IterPinInit, PinInit};
+
+use crate::ptr::RawThinPtr;
+
+use super::{ThinPinVec, VecData};
+
+/// A draining iterator for [`ThinPinVec`]
+///
+/// The elements are yielded in place, and any elements which aren't yielded are
+/// dropped in place. The tail of the vector is moved back into place with [`PinMoveCtor`]
+///
+/// Since the tail is moved over the drained elements when the drain is dropped, the yielded
+/// elements borrow the drain, so this isn't an [`Iterator`]
+///
+/// ```compile_fail
+/// # use thin::pin_vec::ThinPinVec;
+/// let mut vec = ThinPinVec::<i32>::new();
+/// vec.emplace(1);
+/// vec.emplace(2);
+///
+/// let mut drain = vec.drain(..1);
+/// let item = drain.next_pinned();
+/// drop(drain);
+/// drop(item);
+/// ```
+pub struct Drain<'a, T: PinMoveCtor> {
+    pub(super) vec: &'a mut ThinPinVec<T>,
+    pub(super) ptr: RawThinPtr<VecData<T>, usize>,
+    pub(super) iter: IterPinInit<'a, T>,
+    pub(super) tail_offset: usize,
+    pub(super) tail_len: usize,
+}
+
+impl<T: PinMoveCtor> Drop for Drain<'_, T> {
+    fn drop(&mut self) {
+        // if any of these panic, then the allocation is leaked along with the tail
+        // which ensures that the tail is never freed without being dropped
+        self.iter.by_ref().for_each(drop);
+
+        // the vector is only reconstructed once all drained elements are dropped, so
+        // if moving the tail panics, the tail will have been dropped before it's freed
+        let mut vec = ThinPinVec {
+            ptr: self.ptr,
+            _drop: PhantomData,
+        };
+
+        // SAFETY: all drained elements were dropped, so `vec.len()..tail_offset` is vacant
+        // and `tail_offset..tail_offset + tail_len` are the initialized elements of the tail
+        unsafe { vec.move_tail(self.tail_offset, self.tail_len) }
+
+        // the vector was left empty by `ThinPinVec::drain`, so there's nothing to drop
+        *self.vec = vec;
+    }
+}
+
+impl<T: PinMoveCtor> Drain<'_, T> {
+    /// The number of remaining elements in the drain
+    pub fn len(&self) -> usize {
+        self.iter.len()
+    }
+
+    /// Checks if there are no remaining elements in the drain
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the next drained element in place
+    pub fn next_pinned(&mut self) -> Option<PinInit<'_, T>> {
+        let item = self.iter.next()?;
+        // SAFETY: the element is owned by `item`, and it's only reborrowed for the lifetime
+        // of the drain, which doesn't move the tail over it until it's dropped
+        Some(unsafe { PinInit::from_raw(item.into_raw()) })
+    }
+
+    /// Get the last remaining drained element in place
+    pub fn next_back_pinned(&mut self) -> Option<PinInit<'_, T>> {
+        let item = self.iter.next_back()?;
+        // SAFETY: the element is owned by `item`, and it's only reborrowed for the lifetime
+        // of the drain, which doesn't move the tail over it until it's dropped
+        Some(unsafe { PinInit::from_raw(item.into_raw()) })
+    }
+}
+
+/// A consuming iterator for [`ThinPinVec`]
+///
+/// Elements are only moved out by value if they are `Unpin`, otherwise
+/// they can be visited in place with [`IntoIter::next_pinned`]
+pub struct IntoIter<T> {
+    // this vector has a length of zero, so it only frees the allocation
+    pub(super) vec: ThinPinVec<T>,
+    pub(super) start: usize,
+    pub(super) end: usize,
+}
+
+impl<T> Drop for IntoIter<T> {
+    fn drop(&mut self) {
+        if !core::mem::needs_drop::<T>() {
+            return;
+        }
+
+        // SAFETY: the elements `start..end` haven't been yielded yet, so they are still initialized
+        // and they are dropped before the allocation is freed
+        unsafe {
+            let data = self.vec.as_mut_ptr().add(self.start);
+            core::ptr::slice_from_raw_parts_mut(data, self.end - self.start).drop_in_place();
+        }
+    }
+}
+
+impl<T> IntoIter<T> {
+    /// The number of remaining elements in the iterator
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Checks if there are no remaining elements in the iterator
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Get the next element in place
+    pub fn next_pinned(&mut self) -> Option<PinInit<'_, T>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let index = self.start;
+        self.start += 1;
+
+        // SAFETY: the element at `index` is initialized, and is owned by the iterator
+        // it will not be visited again, since `start` was incremented
+        Some(unsafe { PinInit::from_raw(self.vec.as_mut_ptr().add(index)) })
+    }
+
+    /// Get the last remaining element in place
+    pub fn next_back_pinned(&mut self) -> Option<PinInit<'_, T>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        self.end -= 1;
+
+        // SAFETY: the element at `end` is initialized, and is owned by the iterator
+        // it will not be visited again, since `end` was decremented
+        Some(unsafe { PinInit::from_raw(self.vec.as_mut_ptr().add(self.end)) })
+    }
+}
+
+impl<T: Unpin> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_pinned()?.into_inner().into_inner())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
+
+impl<T: Unpin> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        Some(self.next_back_pinned()?.into_inner().into_inner())
+    }
+}
+
+impl<T: Unpin> ExactSizeIterator for IntoIter<T> {}
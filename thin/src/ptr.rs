@@ -4,7 +4,7 @@ use core::{alloc::Layout, marker::PhantomData, ptr::NonNull};
 
 use init::{
     layout_provider::{HasLayoutProvider, LayoutProvider},
-    Ctor, Init,
+    Ctor, Init, PinCtor, TryCtor, TryPinCtor,
 };
 
 /// The Pointee::Metadata for a given type
@@ -82,6 +82,63 @@ impl<T: ?Sized + Ctor<Args>, Args> Ctor<PushHeader<Args>> for WithHeader<T> {
     }
 }
 
+/// Write the metadata into the header, and get the uninitialized value
+fn split_header<'a, T: ?Sized>(
+    uninit: &mut init::Uninit<'a, WithHeader<T>>,
+) -> init::Uninit<'a, T> {
+    let ptr = uninit.as_mut_ptr();
+    // SAFETY: ptr is a dereferencable pointer (guaranteed by `Uninit`), and the metadata
+    // of `WithHeader<T>` is the same as the metadata of `T`
+    unsafe {
+        core::ptr::addr_of_mut!((*ptr).metadata).write(core::ptr::metadata(ptr));
+        init::Uninit::from_raw(core::ptr::addr_of_mut!((*ptr).value))
+    }
+}
+
+impl<T: ?Sized + TryCtor<Args>, Args> TryCtor<PushHeader<Args>> for WithHeader<T> {
+    type Error = T::Error;
+
+    #[inline]
+    fn try_init(
+        mut uninit: init::Uninit<'_, Self>,
+        PushHeader(args): PushHeader<Args>,
+    ) -> Result<init::Init<'_, Self>, Self::Error> {
+        split_header(&mut uninit).try_init(args)?.take_ownership();
+        // SAFETY: the metadata and value were initialized
+        Ok(unsafe { uninit.assume_init() })
+    }
+}
+
+impl<T: ?Sized + PinCtor<Args>, Args> PinCtor<PushHeader<Args>> for WithHeader<T> {
+    #[inline]
+    fn pin_init(
+        mut uninit: init::Uninit<'_, Self>,
+        PushHeader(args): PushHeader<Args>,
+    ) -> init::PinInit<'_, Self> {
+        split_header(&mut uninit).pin_init(args).take_ownership();
+        // SAFETY: the metadata and value were initialized, and the value
+        // will remain in the pinned type-state
+        unsafe { uninit.assume_init().pin() }
+    }
+}
+
+impl<T: ?Sized + TryPinCtor<Args>, Args> TryPinCtor<PushHeader<Args>> for WithHeader<T> {
+    type Error = T::Error;
+
+    #[inline]
+    fn try_pin_init(
+        mut uninit: init::Uninit<'_, Self>,
+        PushHeader(args): PushHeader<Args>,
+    ) -> Result<init::PinInit<'_, Self>, Self::Error> {
+        split_header(&mut uninit)
+            .try_pin_init(args)?
+            .take_ownership();
+        // SAFETY: the metadata and value were initialized, and the value
+        // will remain in the pinned type-state
+        Ok(unsafe { uninit.assume_init().pin() })
+    }
+}
+
 impl<T: ?Sized> RawThinPtr<T> {
     /// Create a raw pointer from an `Init`
     ///
@@ -108,6 +165,17 @@ impl<T: ?Sized> RawThinPtr<T> {
         }
     }
 
+    /// Create a raw pointer from a type-erased pointer
+    ///
+    /// Note: the erased pointer must point to a `WithHeader<T>` to safely
+    /// call any function on this `RawThinPtr` marked unsafe
+    pub const fn from_erased(ptr: NonNull<()>) -> Self {
+        Self {
+            raw: ptr,
+            ty: PhantomData,
+        }
+    }
+
     /// Get the metadata of the pointer
     ///
     /// # Safety
@@ -165,7 +233,7 @@ impl<T: ?Sized> RawThinPtr<T> {
     }
 }
 
-struct Literal<T>(pub T);
+pub(crate) struct Literal<T>(pub T);
 
 impl<T> init::CtorArgs<T> for Literal<T> {
     fn init_into(self, uninit: init::Uninit<'_, T>) -> init::Init<'_, T> {
@@ -0,0 +1,279 @@
+//! A single-threaded reference counted thin pointer
+
+use core::{cell::Cell, fmt, marker::PhantomData, ops::Deref};
+
+use init::{ctor::CloneCtor, layout_provider::HasLayoutProvider, source::Cloned, Ctor};
+
+use crate::{
+    ptr::RawThinPtr,
+    rc_box::{self, RcBox},
+};
+
+type Inner<T> = RcBox<Cell<usize>, T>;
+
+/// A type that's like an `Rc` but guaranteed to be the same representation as a `*mut ()`
+///
+/// The reference counts and the pointer metadata are stored in the allocation
+#[repr(transparent)]
+pub struct ThinRc<T: ?Sized> {
+    ptr: RawThinPtr<Inner<T>>,
+    ty: PhantomData<Inner<T>>,
+}
+
+/// A weak reference to a [`ThinRc`], which doesn't keep the value alive
+#[repr(transparent)]
+pub struct ThinWeak<T: ?Sized> {
+    ptr: RawThinPtr<Inner<T>>,
+    ty: PhantomData<Inner<T>>,
+}
+
+impl<T: ?Sized> ThinRc<T> {
+    /// Construct a new ThinRc
+    pub fn new<Args>(args: Args) -> Self
+    where
+        T: Ctor<Args> + HasLayoutProvider<Args>,
+    {
+        Self {
+            ptr: rc_box::new(args),
+            ty: PhantomData,
+        }
+    }
+
+    fn inner(&self) -> &Inner<T> {
+        // SAFETY: the allocation is kept alive by this strong pointer
+        unsafe { &*self.ptr.as_ptr() }
+    }
+
+    /// The number of strong pointers to this allocation
+    pub fn strong_count(this: &Self) -> usize {
+        this.inner().strong.get()
+    }
+
+    /// The number of weak pointers to this allocation
+    pub fn weak_count(this: &Self) -> usize {
+        // strong pointers collectively hold one weak count
+        this.inner().weak.get() - 1
+    }
+
+    /// Checks if both pointers point to the same allocation
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.ptr.as_erased_ptr() == other.ptr.as_erased_ptr()
+    }
+
+    /// Create a new weak pointer to this allocation
+    pub fn downgrade(this: &Self) -> ThinWeak<T> {
+        let weak = &this.inner().weak;
+        weak.set(weak.get().checked_add(1).expect("weak count overflowed"));
+
+        ThinWeak {
+            ptr: this.ptr,
+            ty: PhantomData,
+        }
+    }
+
+    /// Get a mutable reference to the value, if there are no other `ThinRc` or `ThinWeak` pointers
+    pub fn get_mut(this: &mut Self) -> Option<&mut T> {
+        if Self::is_unique(this) {
+            // SAFETY: there are no other pointers to the allocation
+            Some(unsafe { &mut *core::ptr::addr_of_mut!((*this.ptr.as_mut_ptr()).value) })
+        } else {
+            None
+        }
+    }
+
+    fn is_unique(this: &Self) -> bool {
+        this.inner().strong.get() == 1 && this.inner().weak.get() == 1
+    }
+
+    /// Get a mutable reference to the value
+    ///
+    /// If there are other `ThinRc` or `ThinWeak` pointers to this allocation, then
+    /// the value is cloned into a new allocation first with [`CloneCtor`]
+    pub fn make_mut(this: &mut Self) -> &mut T
+    where
+        T: CloneCtor,
+    {
+        if !Self::is_unique(this) {
+            *this = Self::new(Cloned(&**this));
+        }
+
+        // SAFETY: `this` was just made unique
+        unsafe { Self::get_mut(this).unwrap_unchecked() }
+    }
+}
+
+impl<T: ?Sized> Clone for ThinRc<T> {
+    fn clone(&self) -> Self {
+        let strong = &self.inner().strong;
+        strong.set(
+            strong
+                .get()
+                .checked_add(1)
+                .expect("strong count overflowed"),
+        );
+
+        Self {
+            ptr: self.ptr,
+            ty: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Drop for ThinRc<T> {
+    fn drop(&mut self) {
+        let strong = &self.inner().strong;
+        strong.set(strong.get() - 1);
+
+        if strong.get() != 0 {
+            return;
+        }
+
+        // release the weak count held by all strong pointers, even if dropping the value panics
+        let _weak = ThinWeak {
+            ptr: self.ptr,
+            ty: PhantomData,
+        };
+
+        // SAFETY: this was the last strong pointer, so nothing else can access the value
+        unsafe { core::ptr::addr_of_mut!((*self.ptr.as_mut_ptr()).value).drop_in_place() }
+    }
+}
+
+impl<T: ?Sized> Deref for ThinRc<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner().value
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ThinRc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for ThinRc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized> ThinWeak<T> {
+    fn counts(&self) -> (&Cell<usize>, &Cell<usize>) {
+        // SAFETY: the allocation is kept alive by this weak pointer, and the counts
+        // are valid even if the value was dropped
+        unsafe {
+            let ptr = self.ptr.as_ptr();
+            (
+                &*core::ptr::addr_of!((*ptr).strong),
+                &*core::ptr::addr_of!((*ptr).weak),
+            )
+        }
+    }
+
+    /// Try to get a strong pointer to the value, if it hasn't been dropped yet
+    pub fn upgrade(&self) -> Option<ThinRc<T>> {
+        let (strong, _) = self.counts();
+
+        if strong.get() == 0 {
+            return None;
+        }
+
+        strong.set(
+            strong
+                .get()
+                .checked_add(1)
+                .expect("strong count overflowed"),
+        );
+
+        Some(ThinRc {
+            ptr: self.ptr,
+            ty: PhantomData,
+        })
+    }
+
+    /// The number of strong pointers to this allocation
+    pub fn strong_count(&self) -> usize {
+        self.counts().0.get()
+    }
+}
+
+impl<T: ?Sized> Clone for ThinWeak<T> {
+    fn clone(&self) -> Self {
+        let (_, weak) = self.counts();
+        weak.set(weak.get().checked_add(1).expect("weak count overflowed"));
+
+        Self {
+            ptr: self.ptr,
+            ty: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Drop for ThinWeak<T> {
+    fn drop(&mut self) {
+        let (_, weak) = self.counts();
+        weak.set(weak.get() - 1);
+
+        if weak.get() == 0 {
+            // SAFETY: this was the last pointer to the allocation
+            unsafe { rc_box::dealloc(self.ptr) }
+        }
+    }
+}
+
+#[test]
+fn test_rc() {
+    let mut a = ThinRc::<[u8]>::new(init::slice::ctor::CopyArgsLen(4, 1));
+    assert_eq!(*a, [1; 4]);
+    assert_eq!(
+        core::mem::size_of::<ThinRc<[u8]>>(),
+        core::mem::size_of::<*mut ()>()
+    );
+
+    ThinRc::get_mut(&mut a).unwrap()[0] = 2;
+
+    let b = a.clone();
+    assert_eq!(ThinRc::strong_count(&a), 2);
+    assert!(ThinRc::ptr_eq(&a, &b));
+    assert!(ThinRc::get_mut(&mut a).is_none());
+
+    ThinRc::make_mut(&mut a)[1] = 3;
+    assert!(!ThinRc::ptr_eq(&a, &b));
+    assert_eq!(*a, [2, 3, 1, 1]);
+    assert_eq!(*b, [2, 1, 1, 1]);
+    assert_eq!(ThinRc::strong_count(&b), 1);
+}
+
+#[test]
+fn test_weak() {
+    use alloc::rc::Rc;
+
+    struct Counted(#[allow(dead_code)] Rc<()>);
+
+    impl HasLayoutProvider<&Rc<()>> for Counted {
+        type LayoutProvider = init::layout_provider::SizedLayoutProvider;
+    }
+
+    impl Ctor<&Rc<()>> for Counted {
+        fn init<'a>(uninit: init::Uninit<'a, Self>, rc: &Rc<()>) -> init::Init<'a, Self> {
+            uninit.write(Counted(rc.clone()))
+        }
+    }
+
+    let counter = Rc::new(());
+
+    let a = ThinRc::<Counted>::new(&counter);
+    let weak = ThinRc::downgrade(&a);
+    assert_eq!(ThinRc::weak_count(&a), 1);
+
+    let b = weak.upgrade().unwrap();
+    assert_eq!(weak.strong_count(), 2);
+    drop((a, b));
+
+    // the value is dropped once the last strong pointer is dropped
+    assert_eq!(Rc::strong_count(&counter), 1);
+    assert_eq!(weak.strong_count(), 0);
+    assert!(weak.upgrade().is_none());
+}
@@ -0,0 +1,106 @@
+//! The shared allocation layout for [`ThinRc`](crate::rc::ThinRc) and [`ThinArc`](crate::arc::ThinArc)
+
+use core::{alloc::Layout, cell::Cell, ptr::NonNull, sync::atomic::AtomicUsize};
+
+use init::{
+    layout_provider::{HasLayoutProvider, LayoutProvider},
+    Ctor,
+};
+
+use crate::ptr::{Literal, PushHeader, RawThinPtr, WithHeader};
+
+/// A reference counter which starts at one
+pub(crate) trait Count {
+    const ONE: Self;
+}
+
+impl Count for Cell<usize> {
+    const ONE: Self = Cell::new(1);
+}
+
+impl Count for AtomicUsize {
+    const ONE: Self = AtomicUsize::new(1);
+}
+
+/// The reference counts followed by the value
+///
+/// All strong pointers collectively hold one weak count, so the allocation
+/// is freed once the weak count reaches zero
+#[repr(C)]
+pub(crate) struct RcBox<C, T: ?Sized> {
+    pub strong: C,
+    pub weak: C,
+    pub value: T,
+}
+
+/// A constructor for `RcBox` which starts with one strong pointer
+pub(crate) struct NewRcBox<Args>(pub Args);
+
+pub(crate) struct RcBoxLayoutProvider;
+
+// SAFETY: the layout given by layout_of matches the algorithm used to calculate the layout of
+// repr(C) structs
+unsafe impl<C, T: ?Sized + HasLayoutProvider<Args>, Args>
+    LayoutProvider<RcBox<C, T>, NewRcBox<Args>> for RcBoxLayoutProvider
+{
+    fn layout_of(args: &NewRcBox<Args>) -> Option<Layout> {
+        let value_layout = init::layout_provider::layout_of::<T, Args>(&args.0)?;
+        let counts_layout = Layout::new::<[C; 2]>();
+        let (layout, _) = counts_layout.extend(value_layout).ok()?;
+        Some(layout.pad_to_align())
+    }
+
+    unsafe fn cast(ptr: NonNull<u8>, args: &NewRcBox<Args>) -> NonNull<RcBox<C, T>> {
+        // SAFETY: `Self::layout_of` only returns a layout if `T::layout_of` returns Some
+        let ptr = unsafe { init::layout_provider::cast::<T, Args>(ptr, &args.0) };
+        // SAFETY: `ptr` is non-null
+        unsafe { NonNull::new_unchecked(ptr.as_ptr() as *mut RcBox<C, T>) }
+    }
+}
+
+impl<C, T: ?Sized + HasLayoutProvider<Args>, Args> HasLayoutProvider<NewRcBox<Args>>
+    for RcBox<C, T>
+{
+    type LayoutProvider = RcBoxLayoutProvider;
+}
+
+impl<C: Count, T: ?Sized + Ctor<Args>, Args> Ctor<NewRcBox<Args>> for RcBox<C, T> {
+    #[inline]
+    fn init(
+        uninit: init::Uninit<'_, Self>,
+        NewRcBox(args): NewRcBox<Args>,
+    ) -> init::Init<'_, Self> {
+        init::init_struct! {
+            uninit => Self {
+                strong: Literal(C::ONE),
+                weak: Literal(C::ONE),
+                value: args,
+            }
+        }
+    }
+}
+
+/// Allocate a new `RcBox` with one strong pointer
+pub(crate) fn new<C: Count, T, Args>(args: Args) -> RawThinPtr<RcBox<C, T>>
+where
+    T: ?Sized + Ctor<Args> + HasLayoutProvider<Args>,
+{
+    let bx = init::boxed::boxed::<WithHeader<RcBox<C, T>>, _>(PushHeader(NewRcBox(args)));
+    let bx = alloc::boxed::Box::into_raw(bx);
+    // SAFETY: This pointer came from a box, which is non-null
+    RawThinPtr::from_raw(unsafe { NonNull::new_unchecked(bx) })
+}
+
+/// Free the allocation of an `RcBox`, without dropping the value
+///
+/// # Safety
+///
+/// `ptr` must have been allocated by [`new`], and must not be used after this call
+pub(crate) unsafe fn dealloc<C, T: ?Sized>(ptr: RawThinPtr<RcBox<C, T>>) {
+    // SAFETY: the header is still valid, even if the value was dropped
+    unsafe {
+        let ptr = ptr.as_mut_with_header_ptr();
+        let layout = Layout::for_value_raw(ptr);
+        alloc::alloc::dealloc(ptr.cast(), layout)
+    }
+}
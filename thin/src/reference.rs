@@ -0,0 +1,250 @@
+//! Borrowed thin pointers, which don't care how the `WithHeader<T>` they point to is stored
+
+use core::{
+    fmt,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+
+use init::Init;
+
+use crate::ptr::{RawThinPtr, WithHeader};
+
+/// A type that's like a `&T` but guaranteed to be the same representation as a `*mut ()`
+#[repr(transparent)]
+pub struct ThinRef<'a, T: ?Sized> {
+    ptr: RawThinPtr<T>,
+    lt: PhantomData<&'a T>,
+}
+
+/// A type that's like a `&mut T` but guaranteed to be the same representation as a `*mut ()`
+#[repr(transparent)]
+pub struct ThinMut<'a, T: ?Sized> {
+    ptr: RawThinPtr<T>,
+    lt: PhantomData<&'a mut T>,
+}
+
+// SAFETY: `ThinRef` is a shared reference
+unsafe impl<T: ?Sized + Sync> Send for ThinRef<'_, T> {}
+// SAFETY: `ThinRef` is a shared reference
+unsafe impl<T: ?Sized + Sync> Sync for ThinRef<'_, T> {}
+// SAFETY: `ThinMut` is a unique reference
+unsafe impl<T: ?Sized + Send> Send for ThinMut<'_, T> {}
+// SAFETY: `ThinMut` is a unique reference
+unsafe impl<T: ?Sized + Sync> Sync for ThinMut<'_, T> {}
+
+impl<T: ?Sized> Copy for ThinRef<'_, T> {}
+impl<T: ?Sized> Clone for ThinRef<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+#[track_caller]
+fn check_metadata<T: ?Sized>(header: &WithHeader<T>) {
+    assert!(
+        header.metadata == core::ptr::metadata(header),
+        "the metadata in the header doesn't match the value"
+    );
+}
+
+impl<'a, T: ?Sized> ThinRef<'a, T> {
+    /// Borrow a `WithHeader<T>` as a thin reference
+    ///
+    /// # Panics
+    ///
+    /// If the metadata stored in the header doesn't match the metadata of the value
+    #[track_caller]
+    pub fn new(header: &'a WithHeader<T>) -> Self {
+        check_metadata(header);
+
+        Self {
+            ptr: RawThinPtr::from_raw(NonNull::from(header)),
+            lt: PhantomData,
+        }
+    }
+
+    /// Create a thin reference from a raw pointer
+    ///
+    /// # Safety
+    ///
+    /// The pointer must point to an initialized `WithHeader<T>` with the correct metadata,
+    /// which is valid for reads for `'a`, and isn't mutated for `'a`
+    pub const unsafe fn from_raw(ptr: RawThinPtr<T>) -> Self {
+        Self {
+            ptr,
+            lt: PhantomData,
+        }
+    }
+
+    /// Get the raw pointer
+    pub const fn as_raw(self) -> RawThinPtr<T> {
+        self.ptr
+    }
+
+    /// Convert to a normal reference, which may be a wide pointer
+    pub fn get(self) -> &'a T {
+        // SAFETY: the pointer is valid for reads for `'a`
+        unsafe { &*self.ptr.as_ptr() }
+    }
+}
+
+impl<'a, T: ?Sized> ThinMut<'a, T> {
+    /// Borrow a `WithHeader<T>` as a unique thin reference
+    ///
+    /// # Panics
+    ///
+    /// If the metadata stored in the header doesn't match the metadata of the value
+    #[track_caller]
+    pub fn new(header: &'a mut WithHeader<T>) -> Self {
+        check_metadata(header);
+
+        Self {
+            ptr: RawThinPtr::from_raw(NonNull::from(header)),
+            lt: PhantomData,
+        }
+    }
+
+    /// Leak an initialized `WithHeader<T>` as a unique thin reference, the value will never be dropped
+    ///
+    /// # Panics
+    ///
+    /// If the metadata stored in the header doesn't match the metadata of the value
+    #[track_caller]
+    pub fn from_init(init: Init<'a, WithHeader<T>>) -> Self {
+        check_metadata(init.get());
+
+        Self {
+            ptr: RawThinPtr::from_init(init),
+            lt: PhantomData,
+        }
+    }
+
+    /// Create a unique thin reference from a raw pointer
+    ///
+    /// # Safety
+    ///
+    /// The pointer must point to an initialized `WithHeader<T>` with the correct metadata,
+    /// which is valid for reads and writes for `'a`, and isn't accessed any other way for `'a`
+    pub const unsafe fn from_raw(ptr: RawThinPtr<T>) -> Self {
+        Self {
+            ptr,
+            lt: PhantomData,
+        }
+    }
+
+    /// Get the raw pointer
+    pub const fn as_raw(&self) -> RawThinPtr<T> {
+        self.ptr
+    }
+
+    /// Reborrow as a shorter unique thin reference
+    pub fn reborrow(&mut self) -> ThinMut<'_, T> {
+        ThinMut {
+            ptr: self.ptr,
+            lt: PhantomData,
+        }
+    }
+
+    /// Borrow as a shared thin reference
+    pub fn as_thin_ref(&self) -> ThinRef<'_, T> {
+        ThinRef {
+            ptr: self.ptr,
+            lt: PhantomData,
+        }
+    }
+
+    /// Convert to a normal reference, which may be a wide pointer
+    pub fn into_mut(self) -> &'a mut T {
+        // SAFETY: the pointer is valid for reads and writes, and is unique for `'a`
+        unsafe { &mut *self.ptr.as_mut_ptr() }
+    }
+}
+
+impl<'a, T: ?Sized> From<ThinMut<'a, T>> for ThinRef<'a, T> {
+    fn from(thin: ThinMut<'a, T>) -> Self {
+        ThinRef {
+            ptr: thin.ptr,
+            lt: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for ThinRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+impl<T: ?Sized> Deref for ThinMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the pointer is valid for reads
+        unsafe { &*self.ptr.as_ptr() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for ThinMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: the pointer is valid for reads and writes, and is unique
+        unsafe { &mut *self.ptr.as_mut_ptr() }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ThinRef<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for ThinRef<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ThinMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for ThinMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+#[test]
+fn test_stack() {
+    let mut header = WithHeader {
+        metadata: 3,
+        value: [1u8, 2, 3],
+    };
+    let header: &mut WithHeader<[u8]> = &mut header;
+
+    let mut thin = ThinMut::new(header);
+    assert_eq!(
+        core::mem::size_of_val(&thin),
+        core::mem::size_of::<*mut ()>()
+    );
+    thin[0] = 4;
+
+    let thin = ThinRef::from(thin);
+    assert_eq!(*thin, [4, 2, 3]);
+}
+
+#[test]
+#[should_panic = "the metadata in the header doesn't match the value"]
+fn test_bad_metadata() {
+    let header: &WithHeader<[u8]> = &WithHeader {
+        metadata: 2,
+        value: [1u8, 2, 3],
+    };
+
+    ThinRef::new(header);
+}
@@ -0,0 +1,329 @@
+//! A segmented pinned vector, which never moves it's elements after they are constructed
+//!
+//! The vector grows by allocating new segments (each twice as large as the last),
+//! so elements don't need to implement `PinMoveCtor` and are guaranteed to have a stable
+//! address until they are dropped
+#![forbid(clippy::undocumented_unsafe_blocks)]
+
+use core::{marker::PhantomData, mem::MaybeUninit, pin::Pin, ptr::NonNull};
+
+use alloc::{boxed::Box, vec::Vec};
+use init::{slice::ctor::UninitSliceLen, try_pin_ctor::of_pin_ctor, PinCtor, TryPinCtor};
+
+/// The number of elements in the first segment, each segment after that is twice as large
+const FIRST_SEGMENT_LEN: usize = 4;
+
+/// A pinned vector which stores it's elements in segments which are never reallocated
+pub struct PinSegVec<T> {
+    len: usize,
+    segments: Vec<NonNull<T>>,
+    _drop: PhantomData<T>,
+}
+
+impl<T> Unpin for PinSegVec<T> {}
+
+// SAFETY: `PinSegVec` owns it's elements like a `Vec<T>`
+unsafe impl<T: Send> Send for PinSegVec<T> {}
+// SAFETY: `PinSegVec` owns it's elements like a `Vec<T>`
+unsafe impl<T: Sync> Sync for PinSegVec<T> {}
+
+/// The segment and offset in that segment of `index`
+fn locate(index: usize) -> (usize, usize) {
+    let index = index
+        .checked_add(FIRST_SEGMENT_LEN)
+        .expect("index out of range");
+    let segment = (index.ilog2() - FIRST_SEGMENT_LEN.ilog2()) as usize;
+    (segment, index - segment_len(segment))
+}
+
+fn segment_len(segment: usize) -> usize {
+    FIRST_SEGMENT_LEN << segment
+}
+
+impl<T> Drop for PinSegVec<T> {
+    fn drop(&mut self) {
+        if core::mem::needs_drop::<T>() {
+            let mut remaining = self.len;
+
+            for (i, &segment) in self.segments.iter().enumerate() {
+                let len = remaining.min(segment_len(i));
+                remaining -= len;
+
+                // SAFETY: the first `len` elements of each segment are initialized
+                // if this panics, then the segments are leaked, so any elements which
+                // weren't dropped will never be freed
+                unsafe {
+                    core::ptr::slice_from_raw_parts_mut(segment.as_ptr(), len).drop_in_place()
+                }
+            }
+        }
+
+        for (i, &segment) in self.segments.iter().enumerate() {
+            let segment = core::ptr::slice_from_raw_parts_mut(
+                segment.as_ptr().cast::<MaybeUninit<T>>(),
+                segment_len(i),
+            );
+
+            // SAFETY: each segment was allocated in `PinSegVec::try_emplace` from a box with this length
+            drop(unsafe { Box::from_raw(segment) })
+        }
+    }
+}
+
+impl<T> Default for PinSegVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> PinSegVec<T> {
+    /// Create a new segmented vector
+    pub const fn new() -> Self {
+        Self {
+            len: 0,
+            segments: Vec::new(),
+            _drop: PhantomData,
+        }
+    }
+
+    /// The number of elements in the vector
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the vector has no elements
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of elements the vector can hold without allocating another segment
+    pub fn capacity(&self) -> usize {
+        (0..self.segments.len()).map(segment_len).sum()
+    }
+
+    fn slot(&self, index: usize) -> *mut T {
+        let (segment, offset) = locate(index);
+        // SAFETY: `offset` is in bounds of the segment, by construction of `locate`
+        unsafe { self.segments[segment].as_ptr().add(offset) }
+    }
+
+    /// Try to construct and push a value in place, and get a pinned reference to it
+    pub fn try_emplace<Args>(&mut self, args: Args) -> Result<Pin<&mut T>, T::Error>
+    where
+        T: TryPinCtor<Args>,
+    {
+        let (segment, _) = locate(self.len);
+
+        if segment == self.segments.len() {
+            let bx =
+                init::boxed::boxed::<[MaybeUninit<T>], _>(UninitSliceLen(segment_len(segment)));
+            let ptr = Box::into_raw(bx).cast::<T>();
+            // SAFETY: this pointer came from a box, which is non-null
+            self.segments.push(unsafe { NonNull::new_unchecked(ptr) });
+        }
+
+        // SAFETY: the slot at `len` is in bounds of an allocated segment, and isn't initialized
+        let uninit = unsafe { init::Uninit::from_raw(self.slot(self.len)) };
+        let init = uninit.try_pin_init(args)?;
+
+        // the vector will take ownership of the value
+        let ptr = init.into_raw();
+        self.len += 1;
+
+        // SAFETY: the value was just initialized, and will never be moved
+        Ok(unsafe { Pin::new_unchecked(&mut *ptr) })
+    }
+
+    /// Construct and push a value in place, and get a pinned reference to it
+    pub fn emplace<Args>(&mut self, args: Args) -> Pin<&mut T>
+    where
+        T: PinCtor<Args>,
+    {
+        match self.try_emplace(of_pin_ctor(args)) {
+            Ok(value) => value,
+            Err(inf) => match inf {},
+        }
+    }
+
+    /// Get a pinned reference to the element at `index`
+    pub fn get(&self, index: usize) -> Option<Pin<&T>> {
+        if index >= self.len {
+            return None;
+        }
+
+        // SAFETY: all elements before `len` are initialized and pinned
+        Some(unsafe { Pin::new_unchecked(&*self.slot(index)) })
+    }
+
+    /// Get a pinned mutable reference to the element at `index`
+    pub fn get_mut(&mut self, index: usize) -> Option<Pin<&mut T>> {
+        if index >= self.len {
+            return None;
+        }
+
+        // SAFETY: all elements before `len` are initialized and pinned
+        Some(unsafe { Pin::new_unchecked(&mut *self.slot(index)) })
+    }
+
+    /// An iterator over pinned references to all elements
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            vec: self,
+            index: 0,
+        }
+    }
+
+    /// An iterator over pinned mutable references to all elements
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            len: self.len,
+            vec: self,
+            index: 0,
+            lt: PhantomData,
+        }
+    }
+}
+
+impl<T> core::ops::Index<usize> for PinSegVec<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        match self.get(index) {
+            Some(value) => Pin::get_ref(value),
+            None => index_out_of_bounds(index, self.len),
+        }
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn index_out_of_bounds(index: usize, len: usize) -> ! {
+    panic!("index out of bounds: the len is {len} but the index is {index}")
+}
+
+/// An iterator over pinned references to the elements of a [`PinSegVec`]
+pub struct Iter<'a, T> {
+    vec: &'a PinSegVec<T>,
+    index: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = Pin<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.vec.get(self.index)?;
+        self.index += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.vec.len - self.index;
+        (len, Some(len))
+    }
+}
+
+/// An iterator over pinned mutable references to the elements of a [`PinSegVec`]
+pub struct IterMut<'a, T> {
+    vec: *const PinSegVec<T>,
+    len: usize,
+    index: usize,
+    lt: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = Pin<&'a mut T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index == self.len {
+            return None;
+        }
+
+        // SAFETY: the vector is borrowed mutably for `'a`, and each element is
+        // only yielded once, so these references don't alias
+        let value = unsafe { &mut *(*self.vec).slot(self.index) };
+        self.index += 1;
+
+        // SAFETY: all elements before `len` are initialized and pinned
+        Some(unsafe { Pin::new_unchecked(value) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len - self.index;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> IntoIterator for &'a PinSegVec<T> {
+    type Item = Pin<&'a T>;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut PinSegVec<T> {
+    type Item = Pin<&'a mut T>;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[test]
+fn test_seg_vec_address_stability() {
+    use core::marker::PhantomPinned;
+
+    struct Immovable {
+        value: usize,
+        _pin: PhantomPinned,
+    }
+
+    impl PinCtor<usize> for Immovable {
+        fn pin_init(uninit: init::Uninit<'_, Self>, value: usize) -> init::PinInit<'_, Self> {
+            uninit
+                .write(Immovable {
+                    value,
+                    _pin: PhantomPinned,
+                })
+                .pin()
+        }
+    }
+
+    let mut vec = PinSegVec::<Immovable>::new();
+    let mut addresses = Vec::new();
+
+    for i in 0..100 {
+        let value = vec.emplace(i);
+        addresses.push(&*value as *const Immovable);
+    }
+
+    assert_eq!(vec.len(), 100);
+    assert_eq!(vec.capacity(), 124);
+
+    for (i, value) in vec.iter().enumerate() {
+        assert_eq!(value.value, i);
+        assert_eq!(&*value as *const Immovable, addresses[i]);
+    }
+
+    assert_eq!(vec[42].value, 42);
+    assert!(vec.get(100).is_none());
+}
+
+#[test]
+fn test_seg_vec_drop() {
+    use alloc::rc::Rc;
+
+    let counter = Rc::new(());
+
+    let mut vec = PinSegVec::<Rc<()>>::new();
+
+    for _ in 0..10 {
+        vec.emplace(init::pin_ctor::pin_ctor(|u| u.write(counter.clone()).pin()));
+    }
+
+    assert_eq!(Rc::strong_count(&counter), 11);
+    drop(vec);
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
@@ -0,0 +1,434 @@
+//! A thin vector which stores a small number of elements inline before spilling to the heap
+#![forbid(clippy::undocumented_unsafe_blocks)]
+
+use core::{
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut, RangeBounds},
+};
+
+use init::{ctor::MoveCtor, Ctor, Init};
+
+use crate::vec::{self, new_capacity, ThinVec};
+
+/// A vector which stores up to `N` elements inline, and spills to a [`ThinVec`] after that
+pub struct SmallThinVec<T, const N: usize> {
+    repr: Repr<T, N>,
+}
+
+enum Repr<T, const N: usize> {
+    Inline {
+        len: usize,
+        data: [MaybeUninit<T>; N],
+    },
+    Heap(ThinVec<T>),
+}
+
+impl<T, const N: usize> Drop for SmallThinVec<T, N> {
+    fn drop(&mut self) {
+        if let Repr::Inline { len, data } = &mut self.repr {
+            let data = core::ptr::slice_from_raw_parts_mut(data.as_mut_ptr().cast::<T>(), *len);
+            // SAFETY: the first `len` elements are initialized, and are never used again
+            unsafe { data.drop_in_place() }
+        }
+    }
+}
+
+impl<T, const N: usize> Default for SmallThinVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> SmallThinVec<T, N> {
+    /// Create a new vector, which doesn't allocate until it holds more than `N` elements
+    pub const fn new() -> Self {
+        Self {
+            repr: Repr::Inline {
+                len: 0,
+                data: [const { MaybeUninit::uninit() }; N],
+            },
+        }
+    }
+
+    /// Create a new vector with the given capacity
+    ///
+    /// This only allocates if `capacity` is larger than `N`
+    pub fn with_capacity(capacity: usize) -> Self {
+        if capacity <= N {
+            Self::new()
+        } else {
+            Self {
+                repr: Repr::Heap(ThinVec::with_capacity(capacity)),
+            }
+        }
+    }
+
+    /// Checks if the elements are stored on the heap
+    pub fn is_spilled(&self) -> bool {
+        matches!(self.repr, Repr::Heap(_))
+    }
+
+    pub fn capacity(&self) -> usize {
+        match &self.repr {
+            Repr::Inline { .. } => N,
+            Repr::Heap(vec) => vec.capacity(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.repr {
+            Repr::Inline { len, .. } => *len,
+            Repr::Heap(vec) => vec.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    pub fn as_ptr(&self) -> *const T {
+        match &self.repr {
+            Repr::Inline { data, .. } => data.as_ptr().cast(),
+            Repr::Heap(vec) => vec.as_ptr(),
+        }
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        match &mut self.repr {
+            Repr::Inline { data, .. } => data.as_mut_ptr().cast(),
+            Repr::Heap(vec) => vec.as_mut_ptr(),
+        }
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: the first `len` elements are initialized
+        unsafe { core::slice::from_raw_parts(self.as_ptr(), self.len()) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        let len = self.len();
+        // SAFETY: the first `len` elements are initialized
+        unsafe { core::slice::from_raw_parts_mut(self.as_mut_ptr(), len) }
+    }
+
+    /// Remove the elements in `range` from the vector
+    ///
+    /// Like [`ThinVec::drain`], any elements which weren't yielded by the
+    /// iterator are kept in the vector
+    pub fn drain(&mut self, range: impl RangeBounds<usize>) -> Drain<'_, T> {
+        let (len, data) = match &mut self.repr {
+            Repr::Inline { len, data } => (len, data),
+            Repr::Heap(vec) => {
+                return Drain {
+                    inner: DrainInner::Heap(vec.drain(range)),
+                }
+            }
+        };
+
+        let old_len = *len;
+        let range = core::slice::range(range, ..old_len);
+        let data = data.as_mut_ptr().cast::<T>();
+        *len = range.start;
+
+        // SAFETY: the elements in `range` are initialized, and are no longer owned by the vector
+        let iter = unsafe {
+            Init::from_raw(core::ptr::slice_from_raw_parts_mut(
+                data.add(range.start),
+                range.end - range.start,
+            ))
+        }
+        .into_iter();
+
+        Drain {
+            inner: DrainInner::Inline {
+                len,
+                data,
+                iter,
+                tail_offset: range.end,
+                tail_len: old_len - range.end,
+            },
+        }
+    }
+
+    /// Construct and push a value in place
+    ///
+    /// # Safety
+    ///
+    /// The length must not be equal to the capacity
+    pub unsafe fn emplace_unchecked<Args>(&mut self, args: Args)
+    where
+        T: Ctor<Args>,
+    {
+        let (len, data) = match &mut self.repr {
+            Repr::Inline { len, data } => (len, data),
+            // SAFETY: guaranteed by caller
+            Repr::Heap(vec) => return unsafe { vec.emplace_unchecked(args) },
+        };
+
+        // SAFETY: the caller guarantees that there is space for another element
+        let uninit = unsafe { init::Uninit::from_raw(data.as_mut_ptr().add(*len).cast::<T>()) };
+        let init = uninit.init(args);
+
+        *len += 1;
+
+        // the vector will take ownership of the value
+        init.take_ownership();
+    }
+
+    /// Remove the last element from the vector
+    ///
+    /// # Safety
+    ///
+    /// The vector shouldn't be empty
+    pub unsafe fn pop_unchecked(&mut self) -> Init<'_, T> {
+        match &mut self.repr {
+            Repr::Inline { len, data } => {
+                *len -= 1;
+                // SAFETY: the element at `len` is initialized, and is no longer owned by the vector
+                unsafe { Init::from_raw(data.as_mut_ptr().add(*len).cast::<T>()) }
+            }
+            // SAFETY: guaranteed by caller
+            Repr::Heap(vec) => unsafe { vec.pop_unchecked() },
+        }
+    }
+
+    /// Remove the last element from the vector
+    pub fn pop(&mut self) -> Option<Init<'_, T>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        // SAFETY: The vector isn't empty
+        Some(unsafe { self.pop_unchecked() })
+    }
+}
+
+impl<T: MoveCtor, const N: usize> SmallThinVec<T, N> {
+    pub fn reserve(&mut self, additional: usize) {
+        match &mut self.repr {
+            Repr::Inline { len, .. } if N - *len < additional => self.spill(additional),
+            Repr::Inline { .. } => (),
+            Repr::Heap(vec) => vec.reserve(additional),
+        }
+    }
+
+    /// Move the inline elements to the heap, with space for `additional` more elements
+    #[cold]
+    #[inline(never)]
+    fn spill(&mut self, additional: usize) {
+        let Repr::Inline { len, data } = &mut self.repr else {
+            return;
+        };
+
+        let capacity = new_capacity(*len, additional).expect("Could not calculate new capacity");
+        let mut vec = ThinVec::with_capacity(capacity);
+
+        // if moving an element panics, then the elements which weren't moved yet are dropped
+        // SAFETY: the first `len` elements are initialized, and are no longer owned by the vector
+        let items = unsafe {
+            Init::from_raw(core::ptr::slice_from_raw_parts_mut(
+                data.as_mut_ptr().cast::<T>(),
+                core::mem::take(len),
+            ))
+        };
+
+        for item in items {
+            // SAFETY: `vec` has space for all of the inline elements
+            unsafe { vec.emplace_unchecked(item) }
+        }
+
+        self.repr = Repr::Heap(vec);
+    }
+
+    /// Construct and push a value in place
+    pub fn emplace<Args>(&mut self, args: Args)
+    where
+        T: Ctor<Args>,
+    {
+        if self.is_full() {
+            self.reserve(1);
+        }
+
+        // SAFETY: just reserved enough space
+        unsafe { self.emplace_unchecked(args) }
+    }
+
+    /// Move the elements into a [`ThinVec`]
+    pub fn into_thin_vec(mut self) -> ThinVec<T> {
+        if !self.is_spilled() {
+            self.spill(0);
+        }
+
+        match core::mem::replace(&mut self.repr, Repr::Heap(ThinVec::new())) {
+            Repr::Heap(vec) => vec,
+            // SAFETY: the vector was just spilled
+            Repr::Inline { .. } => unsafe { core::hint::unreachable_unchecked() },
+        }
+    }
+}
+
+impl<T, const N: usize> From<ThinVec<T>> for SmallThinVec<T, N> {
+    fn from(vec: ThinVec<T>) -> Self {
+        Self {
+            repr: Repr::Heap(vec),
+        }
+    }
+}
+
+impl<T, const N: usize> Deref for SmallThinVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> DerefMut for SmallThinVec<T, N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_slice()
+    }
+}
+
+/// A draining iterator for [`SmallThinVec`]
+pub struct Drain<'a, T> {
+    inner: DrainInner<'a, T>,
+}
+
+enum DrainInner<'a, T> {
+    Inline {
+        len: &'a mut usize,
+        data: *mut T,
+        iter: init::IterInit<'a, T>,
+        tail_offset: usize,
+        tail_len: usize,
+    },
+    Heap(vec::iter::Drain<'a, T>),
+}
+
+impl<T> Drop for Drain<'_, T> {
+    fn drop(&mut self) {
+        let DrainInner::Inline {
+            len,
+            data,
+            iter,
+            tail_offset,
+            tail_len,
+        } = &mut self.inner
+        else {
+            return;
+        };
+
+        // SAFETY: the iterator is left empty, which is safe to drop
+        let mut remaining = unsafe { iter.take_ownership() }.into_remaining();
+        let rem_len = remaining.len();
+        let rem_start = remaining.as_mut_ptr().cast::<T>();
+        // the vector will take ownership of the remaining elements
+        remaining.take_ownership();
+
+        // SAFETY: `len <= rem_start` and `len + rem_len <= tail_offset`, so both copies stay
+        // inside the inline storage, and the vector owns exactly the first `len` elements after
+        unsafe {
+            let dest = data.add(**len);
+            dest.copy_from(rem_start, rem_len);
+            dest.add(rem_len)
+                .copy_from(data.add(*tail_offset), *tail_len);
+        }
+
+        **len += rem_len + *tail_len;
+    }
+}
+
+impl<'a, T> Iterator for Drain<'a, T> {
+    type Item = Init<'a, T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            DrainInner::Inline { iter, .. } => iter.next(),
+            DrainInner::Heap(drain) => drain.next(),
+        }
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Drain<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            DrainInner::Inline { iter, .. } => iter.next_back(),
+            DrainInner::Heap(drain) => drain.next_back(),
+        }
+    }
+}
+
+#[test]
+fn test_small_vec() {
+    let mut v = SmallThinVec::<u32, 4>::new();
+
+    for i in 0..4 {
+        v.emplace(i);
+    }
+
+    assert!(!v.is_spilled());
+    assert_eq!(v.as_slice(), [0, 1, 2, 3]);
+
+    v.emplace(4);
+    assert!(v.is_spilled());
+    assert_eq!(v.as_slice(), [0, 1, 2, 3, 4]);
+    assert_eq!(v.pop().map(Init::into_inner), Some(4));
+
+    let v = v.into_thin_vec();
+    assert_eq!(v.as_slice(), [0, 1, 2, 3]);
+}
+
+#[test]
+fn test_small_vec_drain() {
+    let mut v = SmallThinVec::<i32, 8>::new();
+
+    for i in [10, 20, 30, 40, 50] {
+        v.emplace(i);
+    }
+
+    v.drain(..);
+    assert_eq!(v.as_slice(), [10, 20, 30, 40, 50]);
+
+    v.drain(1..).next();
+    assert_eq!(v.as_slice(), [10, 30, 40, 50]);
+
+    v.drain(1..3).next_back();
+    assert_eq!(v.as_slice(), [10, 30, 50]);
+
+    v.drain(..2).for_each(drop);
+    assert_eq!(v.as_slice(), [50]);
+    assert!(!v.is_spilled());
+}
+
+#[test]
+fn test_small_vec_spill_move() {
+    use alloc::rc::Rc;
+
+    struct Counted(#[allow(dead_code)] Rc<()>);
+
+    impl MoveCtor for Counted {
+        fn move_ctor<'this>(uninit: init::Uninit<'this, Self>, p: Init<Self>) -> Init<'this, Self> {
+            uninit.write(p.into_inner())
+        }
+    }
+
+    let counter = Rc::new(());
+    let mut v = SmallThinVec::<Counted, 2>::new();
+
+    for _ in 0..5 {
+        v.emplace(init::ctor(|u| u.write(Counted(counter.clone()))));
+    }
+
+    assert!(v.is_spilled());
+    assert_eq!(Rc::strong_count(&counter), 6);
+    drop(v.pop());
+    assert_eq!(Rc::strong_count(&counter), 5);
+    drop(v);
+    assert_eq!(Rc::strong_count(&counter), 1);
+}
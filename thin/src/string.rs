@@ -0,0 +1,331 @@
+//! A thin string which stores the length and capacity on the heap
+
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+    ops::{Deref, DerefMut},
+};
+
+use alloc::string::String;
+
+use crate::vec::ThinVec;
+
+/// A growable UTF-8 string which is guaranteed to be the same size as a `*mut ()`
+pub struct ThinString {
+    vec: ThinVec<u8>,
+}
+
+impl ThinString {
+    /// Create a new empty string
+    pub const fn new() -> Self {
+        Self {
+            vec: ThinVec::new(),
+        }
+    }
+
+    /// Create a new empty string with at least the given capacity
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            vec: ThinVec::with_capacity(capacity),
+        }
+    }
+
+    /// Convert a vector of bytes to a string if it is valid UTF-8
+    pub fn from_utf8(vec: ThinVec<u8>) -> Result<Self, ThinVec<u8>> {
+        match core::str::from_utf8(vec.as_slice()) {
+            Ok(_) => Ok(Self { vec }),
+            Err(_) => Err(vec),
+        }
+    }
+
+    /// Convert a vector of bytes to a string without checking that it is valid UTF-8
+    ///
+    /// # Safety
+    ///
+    /// The bytes must be valid UTF-8
+    pub unsafe fn from_utf8_unchecked(vec: ThinVec<u8>) -> Self {
+        Self { vec }
+    }
+
+    /// Get the underlying bytes of the string
+    pub fn into_bytes(self) -> ThinVec<u8> {
+        self.vec
+    }
+
+    /// The length of the string in bytes
+    pub fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    /// Checks if the string is empty
+    pub fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
+
+    /// The number of bytes the string can hold without reallocating
+    pub fn capacity(&self) -> usize {
+        self.vec.capacity()
+    }
+
+    /// Reserve space for at least `additional` more bytes
+    pub fn reserve(&mut self, additional: usize) {
+        self.vec.reserve(additional)
+    }
+
+    /// Get the string as a `str`
+    pub fn as_str(&self) -> &str {
+        // SAFETY: the bytes are always valid UTF-8
+        unsafe { core::str::from_utf8_unchecked(self.vec.as_slice()) }
+    }
+
+    /// Get the string as a mutable `str`
+    pub fn as_mut_str(&mut self) -> &mut str {
+        // SAFETY: the bytes are always valid UTF-8, and `str` can't be used to break that
+        unsafe { core::str::from_utf8_unchecked_mut(self.vec.as_mut_slice()) }
+    }
+
+    /// Append a string slice to the end of the string
+    pub fn push_str(&mut self, s: &str) {
+        self.vec.extend_from_slice(s.as_bytes())
+    }
+
+    /// Append a character to the end of the string
+    pub fn push(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Remove the last character from the string
+    pub fn pop(&mut self) -> Option<char> {
+        let c = self.as_str().chars().next_back()?;
+        self.truncate(self.len() - c.len_utf8());
+        Some(c)
+    }
+
+    /// Shorten the string to `new_len` bytes, does nothing if `new_len >= len`
+    ///
+    /// # Panics
+    ///
+    /// If `new_len` doesn't lie on a `char` boundary
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len < self.len() {
+            assert!(
+                self.as_str().is_char_boundary(new_len),
+                "`new_len` (is {new_len}) should lie on a char boundary"
+            );
+            self.vec.drain(new_len..).for_each(drop);
+        }
+    }
+
+    /// Remove all characters from the string, but keep the allocation
+    pub fn clear(&mut self) {
+        self.truncate(0)
+    }
+}
+
+impl Default for ThinString {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for ThinString {
+    fn clone(&self) -> Self {
+        Self::from(self.as_str())
+    }
+}
+
+impl Deref for ThinString {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl DerefMut for ThinString {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_str()
+    }
+}
+
+impl AsRef<str> for ThinString {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl AsRef<[u8]> for ThinString {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl core::borrow::Borrow<str> for ThinString {
+    fn borrow(&self) -> &str {
+        self
+    }
+}
+
+impl fmt::Write for ThinString {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.push(c);
+        Ok(())
+    }
+}
+
+impl fmt::Display for ThinString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Debug for ThinString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl Hash for ThinString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl PartialEq for ThinString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for ThinString {}
+
+impl PartialEq<str> for ThinString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for ThinString {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for ThinString {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl PartialOrd for ThinString {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ThinString {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl From<&str> for ThinString {
+    fn from(s: &str) -> Self {
+        let mut string = Self::with_capacity(s.len());
+        string.push_str(s);
+        string
+    }
+}
+
+impl From<String> for ThinString {
+    fn from(s: String) -> Self {
+        Self::from(s.as_str())
+    }
+}
+
+impl From<ThinString> for String {
+    fn from(s: ThinString) -> Self {
+        String::from(s.as_str())
+    }
+}
+
+impl From<&ThinString> for String {
+    fn from(s: &ThinString) -> Self {
+        String::from(s.as_str())
+    }
+}
+
+impl Extend<char> for ThinString {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        iter.for_each(|c| self.push(c))
+    }
+}
+
+impl<'a> Extend<&'a str> for ThinString {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|s| self.push_str(s))
+    }
+}
+
+impl FromIterator<char> for ThinString {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        let mut string = Self::new();
+        string.extend(iter);
+        string
+    }
+}
+
+#[test]
+fn test_string() {
+    use core::fmt::Write;
+
+    let mut s = ThinString::new();
+    assert!(s.is_empty());
+    assert_eq!(
+        core::mem::size_of::<ThinString>(),
+        core::mem::size_of::<*mut ()>()
+    );
+
+    s.push_str("hello");
+    s.push(' ');
+    write!(s, "world {}", 42).unwrap();
+    assert_eq!(s, "hello world 42");
+
+    assert_eq!(s.pop(), Some('2'));
+    s.truncate(5);
+    assert_eq!(s, "hello");
+
+    s.push('é');
+    assert_eq!(s.pop(), Some('é'));
+    assert_eq!(s.len(), 5);
+
+    let string = String::from(s.clone());
+    assert_eq!(string, "hello");
+    assert_eq!(ThinString::from(string), s);
+
+    let (a, b) = (ThinString::from("a"), ThinString::from("b"));
+    assert!(a < b);
+
+    s.clear();
+    assert!(s.is_empty());
+    assert_eq!(s.pop(), None);
+}
+
+#[test]
+fn test_from_utf8() {
+    let mut vec = ThinVec::<u8>::new();
+    vec.extend_from_slice(b"abc");
+    let s = ThinString::from_utf8(vec).ok().unwrap();
+    assert_eq!(s, "abc");
+
+    let mut vec = ThinVec::<u8>::new();
+    vec.extend_from_slice(&[0xff, 0xfe]);
+    assert!(ThinString::from_utf8(vec).is_err());
+}
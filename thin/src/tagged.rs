@@ -0,0 +1,270 @@
+//! Thin pointers which store a small tag in the unused low bits of the address
+//!
+//! The allocation behind a thin pointer is a [`WithHeader<T>`], so it's aligned to
+//! `align_of::<WithHeader<T>>()`. For sized types this is checked exactly, and for slices,
+//! `str` and trait objects it's at least the alignment of the `usize`-sized metadata,
+//! which leaves 2 (or 3 on 64-bit targets) bits free.
+
+use core::{
+    fmt,
+    marker::PhantomData,
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+
+use crate::{
+    boxed::ThinBox,
+    ptr::{Metadata, RawThinPtr, WithHeader},
+};
+
+/// The minimum alignment of the `WithHeader<T>` behind a thin pointer
+///
+/// This is exact for sized types, and the alignment of the metadata for unsized types
+const fn header_align<T: ?Sized>() -> usize {
+    if core::mem::size_of::<Metadata<T>>() == 0 {
+        // SAFETY: only sized types have zero-sized metadata, which is `()` and valid when zeroed,
+        // and the alignment of a sized type doesn't depend on the pointer
+        unsafe {
+            let ptr = core::ptr::from_raw_parts::<WithHeader<T>>(
+                core::ptr::null::<()>(),
+                MaybeUninit::zeroed().assume_init(),
+            );
+            core::mem::align_of_val_raw(ptr)
+        }
+    } else {
+        core::mem::align_of::<Metadata<T>>()
+    }
+}
+
+/// A raw thin pointer with a `BITS`-bit tag stored in the low bits of the address
+///
+/// This is guaranteed to have the same representation as a `*mut ()`
+#[repr(transparent)]
+pub struct RawTaggedThinPtr<T: ?Sized, const BITS: usize> {
+    raw: NonNull<()>,
+    ty: PhantomData<RawThinPtr<T>>,
+}
+
+impl<T: ?Sized, const BITS: usize> Copy for RawTaggedThinPtr<T, BITS> {}
+impl<T: ?Sized, const BITS: usize> Clone for RawTaggedThinPtr<T, BITS> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized, const BITS: usize> RawTaggedThinPtr<T, BITS> {
+    /// The bits of the address which hold the tag
+    ///
+    /// Using more bits than the alignment of the allocation leaves free fails to compile
+    ///
+    /// ```
+    /// # use thin::{boxed::ThinBox, tagged::TaggedThinBox};
+    /// // a `WithHeader<u16>` is aligned to 2 bytes, which leaves 1 bit for the tag
+    /// let tagged = TaggedThinBox::<_, 1>::new(ThinBox::<u16>::new(7), 1);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// # use thin::{boxed::ThinBox, tagged::TaggedThinBox};
+    /// let tagged = TaggedThinBox::<_, 2>::new(ThinBox::<u16>::new(7), 1);
+    /// ```
+    pub const TAG_MASK: usize = {
+        assert!(
+            BITS < usize::BITS as usize && header_align::<T>() >> BITS != 0,
+            "the alignment of the thin allocation doesn't leave enough bits for the tag"
+        );
+        (1 << BITS) - 1
+    };
+
+    /// Create a tagged pointer
+    ///
+    /// # Panics
+    ///
+    /// If the tag doesn't fit in `BITS` bits
+    pub fn new(ptr: RawThinPtr<T>, tag: usize) -> Self {
+        assert!(
+            tag & !Self::TAG_MASK == 0,
+            "the tag doesn't fit in {BITS} bits"
+        );
+
+        Self {
+            // SAFETY: the low bits of the address are always zero, so adding the tag can't make it null
+            raw: unsafe {
+                NonNull::new_unchecked(ptr.as_erased_mut_ptr().map_addr(|addr| addr | tag))
+            },
+            ty: PhantomData,
+        }
+    }
+
+    /// Get the tag
+    pub fn tag(self) -> usize {
+        self.raw.as_ptr().addr() & Self::TAG_MASK
+    }
+
+    /// Replace the tag
+    ///
+    /// # Panics
+    ///
+    /// If the tag doesn't fit in `BITS` bits
+    pub fn with_tag(self, tag: usize) -> Self {
+        Self::new(self.ptr(), tag)
+    }
+
+    /// Get the pointer without the tag
+    pub fn ptr(self) -> RawThinPtr<T> {
+        let ptr = self.raw.as_ptr().map_addr(|addr| addr & !Self::TAG_MASK);
+        // SAFETY: the tag was added to a non-null pointer
+        RawThinPtr::from_erased(unsafe { NonNull::new_unchecked(ptr) })
+    }
+}
+
+impl<T: ?Sized, const BITS: usize> fmt::Debug for RawTaggedThinPtr<T, BITS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawTaggedThinPtr")
+            .field("ptr", &self.ptr().as_erased_ptr())
+            .field("tag", &self.tag())
+            .finish()
+    }
+}
+
+/// A [`ThinBox`] which stores a `BITS`-bit tag in the low bits of the address
+///
+/// This is guaranteed to have the same representation as a `*mut ()`
+#[repr(transparent)]
+pub struct TaggedThinBox<T: ?Sized, const BITS: usize> {
+    ptr: RawTaggedThinPtr<T, BITS>,
+    ty: PhantomData<T>,
+}
+
+// SAFETY: `TaggedThinBox` owns it's value like a `Box<T>`
+unsafe impl<T: ?Sized + Send, const BITS: usize> Send for TaggedThinBox<T, BITS> {}
+// SAFETY: `TaggedThinBox` owns it's value like a `Box<T>`
+unsafe impl<T: ?Sized + Sync, const BITS: usize> Sync for TaggedThinBox<T, BITS> {}
+
+impl<T: ?Sized, const BITS: usize> Drop for TaggedThinBox<T, BITS> {
+    fn drop(&mut self) {
+        // SAFETY: the pointer came from `ThinBox::into_raw`, and is never used again
+        drop(unsafe { ThinBox::from_raw(self.ptr.ptr()) })
+    }
+}
+
+impl<T: ?Sized, const BITS: usize> TaggedThinBox<T, BITS> {
+    /// Tag a ThinBox
+    ///
+    /// # Panics
+    ///
+    /// If the tag doesn't fit in `BITS` bits
+    pub fn new(bx: ThinBox<T>, tag: usize) -> Self {
+        Self {
+            ptr: RawTaggedThinPtr::new(ThinBox::into_raw(bx), tag),
+            ty: PhantomData,
+        }
+    }
+
+    /// Get the tag
+    pub fn tag(&self) -> usize {
+        self.ptr.tag()
+    }
+
+    /// Replace the tag
+    ///
+    /// # Panics
+    ///
+    /// If the tag doesn't fit in `BITS` bits
+    pub fn set_tag(&mut self, tag: usize) {
+        self.ptr = self.ptr.with_tag(tag);
+    }
+
+    /// Split the box into the untagged `ThinBox` and the tag
+    pub fn into_parts(self) -> (ThinBox<T>, usize) {
+        let ptr = core::mem::ManuallyDrop::new(self).ptr;
+        // SAFETY: the pointer came from `ThinBox::into_raw`, and `self` won't be dropped
+        (unsafe { ThinBox::from_raw(ptr.ptr()) }, ptr.tag())
+    }
+
+    /// Get the tagged raw pointer
+    pub fn into_raw(self) -> RawTaggedThinPtr<T, BITS> {
+        core::mem::ManuallyDrop::new(self).ptr
+    }
+
+    /// Create a TaggedThinBox from a raw pointer
+    ///
+    /// # Safety
+    ///
+    /// The pointer must have come from [`TaggedThinBox::into_raw`], and must not be used after this call
+    pub unsafe fn from_raw(ptr: RawTaggedThinPtr<T, BITS>) -> Self {
+        Self {
+            ptr,
+            ty: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized, const BITS: usize> Deref for TaggedThinBox<T, BITS> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: This pointer is valid, allocated, and initialized
+        unsafe { &*self.ptr.ptr().as_ptr() }
+    }
+}
+
+impl<T: ?Sized, const BITS: usize> DerefMut for TaggedThinBox<T, BITS> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: This pointer is valid, allocated, and initialized
+        unsafe { &mut *self.ptr.ptr().as_mut_ptr() }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug, const BITS: usize> fmt::Debug for TaggedThinBox<T, BITS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaggedThinBox")
+            .field("value", &&**self)
+            .field("tag", &self.tag())
+            .finish()
+    }
+}
+
+#[test]
+fn test_tagged() {
+    let bx = ThinBox::<[u16]>::new(init::slice::ctor::CopyArgsLen(3, 5));
+    let mut tagged = TaggedThinBox::<_, 2>::new(bx, 3);
+    assert_eq!(
+        core::mem::size_of_val(&tagged),
+        core::mem::size_of::<*mut ()>()
+    );
+
+    assert_eq!(tagged.tag(), 3);
+    assert_eq!(*tagged, [5, 5, 5]);
+
+    tagged[1] = 6;
+    tagged.set_tag(1);
+    assert_eq!(tagged.tag(), 1);
+
+    let raw = tagged.into_raw();
+    assert_eq!(raw.tag(), 1);
+    // SAFETY: the pointer came from `into_raw`
+    let tagged = unsafe { TaggedThinBox::from_raw(raw.with_tag(2)) };
+
+    let (bx, tag) = tagged.into_parts();
+    assert_eq!(tag, 2);
+    assert_eq!(*bx, [5, 6, 5]);
+}
+
+#[test]
+#[should_panic = "the tag doesn't fit in 2 bits"]
+fn test_tag_too_large() {
+    let bx = ThinBox::<str>::from("hello");
+    TaggedThinBox::<_, 2>::new(bx, 4);
+}
+
+#[test]
+fn test_tagged_sized() {
+    let bx = ThinBox::<u64>::new(7);
+    let tagged = TaggedThinBox::<_, 2>::new(bx, 2);
+
+    assert_eq!(tagged.tag(), 2);
+    assert_eq!(*tagged, 7);
+    assert_eq!(*tagged.into_parts().0, 7);
+}
+
@@ -1,13 +1,18 @@
 //! A thin vector implementation that stores the length and capacity on the heap
 
-mod iter;
+pub(crate) mod iter;
 
 use core::ops::RangeBounds;
-use core::{alloc::Layout, mem::MaybeUninit, ptr::NonNull};
+use core::{
+    alloc::Layout,
+    ffi::c_void,
+    mem::{ManuallyDrop, MaybeUninit},
+    ptr::NonNull,
+};
 
-use alloc::alloc::handle_alloc_error;
+use alloc::{alloc::handle_alloc_error, vec::Vec};
 use init::{
-    ctor::MoveCtor,
+    ctor::{CloneCtor, MoveCtor},
     layout_provider::{HasLayoutProvider, LayoutProvider},
     Ctor, Init,
 };
@@ -15,19 +20,23 @@ use init::{
 use crate::{
     boxed::ThinBox,
     ptr::{PushHeader, RawThinPtr, WithHeader},
+    reference::{ThinMut, ThinRef},
 };
 
 /// A thin vector which stores the length and capacity on the heap
+///
+/// This is guaranteed to have the same representation as a `*mut ()`
+#[repr(transparent)]
 pub struct ThinVec<T> {
     ptr: RawThinPtr<VecData<T>, usize>,
 }
 
 #[repr(C)]
-struct VecDataInner<T: ?Sized> {
-    len: usize,
-    data: T,
+pub(crate) struct VecDataInner<T: ?Sized> {
+    pub(crate) len: usize,
+    pub(crate) data: T,
 }
-type VecData<T> = VecDataInner<[MaybeUninit<T>]>;
+pub(crate) type VecData<T> = VecDataInner<[MaybeUninit<T>]>;
 type VecDataSized<T, const N: usize> = VecDataInner<[MaybeUninit<T>; N]>;
 
 type AllocTy<T> = WithHeader<VecData<T>>;
@@ -56,7 +65,8 @@ impl Drop for RawThinVec {
 
 impl<T> Drop for ThinVec<T> {
     fn drop(&mut self) {
-        if self.capacity() == 0 {
+        // the shared empty header has no capacity, even for zero-sized types
+        if unsafe { (*self.as_header_ptr()).capacity } == 0 {
             return;
         }
 
@@ -111,6 +121,50 @@ impl<T> ThinVec<T> {
         Self { ptr }
     }
 
+    /// The length and elements of the vector, which have the same layout as a `WithHeader<[T]>`
+    fn as_slice_header(&self) -> RawThinPtr<[T]> {
+        // SAFETY: the pointer is valid, even if it points to the shared empty header
+        let data = unsafe { self.ptr.as_ptr() };
+        // SAFETY: `data` is derived from a non-null pointer
+        RawThinPtr::from_erased(unsafe { NonNull::new_unchecked(data.cast::<()>().cast_mut()) })
+    }
+
+    /// Borrow the elements as a thin reference
+    pub fn as_thin_ref(&self) -> ThinRef<'_, [T]> {
+        // SAFETY: the length is followed by `len` initialized elements, and is borrowed from `self`
+        unsafe { ThinRef::from_raw(self.as_slice_header()) }
+    }
+
+    /// Borrow the elements as a unique thin reference
+    ///
+    /// The length of the vector can't be changed through the thin reference
+    pub fn as_thin_mut(&mut self) -> ThinMut<'_, [T]> {
+        // SAFETY: the length is followed by `len` initialized elements, and is uniquely borrowed
+        // from `self`. The length can't be modified through a `ThinMut`, so the shared empty
+        // header is never written to
+        unsafe { ThinMut::from_raw(self.as_slice_header()) }
+    }
+
+    /// Convert the vector into a type-erased pointer, which can be passed through FFI as a `void*`
+    ///
+    /// The pointer must eventually be passed back to [`ThinVec::from_raw_erased`] to free it
+    pub fn into_raw_erased(self) -> *mut c_void {
+        ManuallyDrop::new(self).ptr.as_erased_mut_ptr().cast()
+    }
+
+    /// Create a vector from a type-erased pointer
+    ///
+    /// # Safety
+    ///
+    /// The pointer must have come from [`ThinVec::into_raw_erased`] with the same `T`,
+    /// and must not be used after this call
+    pub unsafe fn from_raw_erased(ptr: *mut c_void) -> Self {
+        Self {
+            // SAFETY: the pointer came from `into_raw_erased`, which never returns null
+            ptr: RawThinPtr::from_erased(unsafe { NonNull::new_unchecked(ptr.cast()) }),
+        }
+    }
+
     fn as_header_ptr(&self) -> *const VecDataHeader<T> {
         self.ptr.as_erased_ptr().cast()
     }
@@ -153,6 +207,11 @@ impl<T> ThinVec<T> {
         unsafe { core::slice::from_raw_parts::<T>(self.as_ptr(), self.len()) }
     }
 
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        let len = self.len();
+        unsafe { core::slice::from_raw_parts_mut::<T>(self.as_mut_ptr(), len) }
+    }
+
     pub fn drain(&mut self, range: impl RangeBounds<usize>) -> iter::Drain<'_, T> {
         let old_len = self.len();
         let range = core::slice::range(range, ..old_len);
@@ -161,7 +220,10 @@ impl<T> ThinVec<T> {
 
         let init = unsafe {
             let ptr = self.ptr.as_mut_ptr();
-            (*ptr).len = range.start;
+            // empty vectors may point to the shared empty header, which must not be written to
+            if old_len != 0 {
+                (*ptr).len = range.start;
+            }
             let items = core::ptr::addr_of_mut!((*ptr).data).cast::<T>();
             Init::from_raw(core::ptr::slice_from_raw_parts_mut(
                 items.add(range.start),
@@ -198,6 +260,30 @@ impl<T> ThinVec<T> {
         init.take_ownership();
     }
 
+    /// Construct `len` values past the end of the vector in place, and push them
+    ///
+    /// # Safety
+    ///
+    /// The vector must have space for at least `len` more elements
+    unsafe fn init_tail<Args>(&mut self, len: usize, args: Args)
+    where
+        [T]: Ctor<Args>,
+    {
+        if len == 0 {
+            return;
+        }
+
+        let ptr = unsafe { self.as_mut_ptr().add(self.len()) };
+        let uninit =
+            unsafe { init::Uninit::from_raw(core::ptr::slice_from_raw_parts_mut(ptr, len)) };
+        let init = uninit.init(args);
+
+        unsafe { (*self.as_header_mut_ptr()).len += len }
+
+        // the vector will take ownership of the values
+        init.take_ownership();
+    }
+
     /// Remove the last element from the vector
     ///
     /// # Safety
@@ -227,7 +313,7 @@ impl<T> ThinVec<T> {
     }
 }
 
-fn new_capacity(capacity: usize, additional: usize) -> Option<usize> {
+pub(crate) fn new_capacity(capacity: usize, additional: usize) -> Option<usize> {
     let expected_capacity = capacity.checked_add(additional)?;
     let new_capacity = capacity.wrapping_mul(2);
     let min_capacity = 4;
@@ -329,11 +415,218 @@ impl<T: MoveCtor> ThinVec<T> {
         // SAFETY: just reserved enough space
         unsafe { self.emplace_unchecked(args) }
     }
+
+    /// Move all elements of `other` to the end of this vector, leaving `other` empty
+    pub fn append(&mut self, other: &mut Self) {
+        let len = other.len();
+        self.reserve(len);
+
+        let mut drain = other.drain(..);
+
+        // SAFETY: just reserved enough space
+        unsafe { self.init_tail(len, drain.take_remaining()) }
+    }
+
+    /// Split the vector in two at the given index
+    ///
+    /// Returns a vector containing the elements `at..`, and leaves
+    /// the elements `..at` in this vector
+    ///
+    /// # Panics
+    ///
+    /// if `at > len`
+    pub fn split_off(&mut self, at: usize) -> Self {
+        let len = self.len();
+        assert!(
+            at <= len,
+            "`at` split index (is {at}) should be <= len (is {len})"
+        );
+
+        let mut other = Self::with_capacity(len - at);
+
+        let mut drain = self.drain(at..);
+
+        // SAFETY: `other` has space for all of the drained elements
+        unsafe { other.init_tail(len - at, drain.take_remaining()) }
+
+        other
+    }
+
+    /// Replace the elements in `range` with values constructed from `replace_with`
+    ///
+    /// The removed elements are yielded by the returned iterator, and the new elements
+    /// are constructed in place when it is dropped
+    pub fn splice<I>(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        replace_with: I,
+    ) -> iter::Splice<'_, T, I::IntoIter>
+    where
+        I: IntoIterator,
+        T: Ctor<I::Item>,
+    {
+        let vec: *mut Self = self;
+
+        iter::Splice {
+            // SAFETY: `vec` came from `self`, and the splice borrows `self` for as long as the drain
+            drain: unsafe { (*vec).drain(range) },
+            replace_with: replace_with.into_iter(),
+            vec,
+        }
+    }
 }
 
-struct WithCapacity(usize);
+impl<T: CloneCtor> ThinVec<T> {
+    /// Clone and push all elements of `slice` to the end of the vector
+    ///
+    /// If `T::IS_CLONE_TRIVIAL` holds, then this is a single `copy_nonoverlapping`
+    pub fn extend_from_slice(&mut self, slice: &[T]) {
+        self.reserve(slice.len());
 
-struct WithCapacityLayoutProvider;
+        // SAFETY: just reserved enough space
+        unsafe { self.init_tail(slice.len(), slice) }
+    }
+}
+
+/// Checks if a `Vec<T>`'s allocation can be reused by a `ThinVec<T>` (and vice versa)
+fn can_reuse_alloc<T: MoveCtor>() -> bool {
+    core::mem::size_of::<T>() != 0
+        && core::mem::align_of::<T>() >= core::mem::align_of::<usize>()
+        && T::IS_MOVE_TRIVIAL.get()
+}
+
+impl<T: MoveCtor> From<Vec<T>> for ThinVec<T> {
+    /// Move the elements out of the `Vec`
+    ///
+    /// If `T::IS_MOVE_TRIVIAL` holds and `T` is at least as aligned as a `usize`,
+    /// then the allocation is reused, otherwise the elements are moved with [`MoveCtor`]
+    fn from(mut vec: Vec<T>) -> Self {
+        let len = vec.len();
+        let capacity = vec.capacity();
+
+        if capacity == 0 {
+            return Self::new();
+        }
+
+        if can_reuse_alloc::<T>() {
+            let layout = Layout::array::<T>(capacity);
+            let new_layout = init::layout_provider::layout_of::<AllocTy<T>, _>(&PushHeader(
+                WithCapacity(capacity),
+            ));
+
+            if let (Ok(layout), Some(new_layout)) = (layout, new_layout) {
+                let mut vec = ManuallyDrop::new(vec);
+
+                // SAFETY: the vec was allocated by the global allocator with an array layout, which has the
+                // same size and alignment as `layout`, and the elements are trivially movable
+                unsafe {
+                    let ptr =
+                        alloc::alloc::realloc(vec.as_mut_ptr().cast(), layout, new_layout.size());
+                    let Some(ptr) = NonNull::new(ptr) else {
+                        handle_alloc_error(new_layout);
+                    };
+
+                    let ptr = init::layout_provider::cast::<AllocTy<T>, _>(
+                        ptr,
+                        &PushHeader(WithCapacity(capacity)),
+                    );
+
+                    let raw = ptr.as_ptr();
+                    let data = core::ptr::addr_of_mut!((*raw).value.data).cast::<T>();
+                    data.copy_from(raw.cast::<T>(), len);
+                    (*raw).metadata = capacity;
+                    (*raw).value.len = len;
+
+                    return Self {
+                        ptr: RawThinPtr::from_raw(ptr),
+                    };
+                }
+            }
+        }
+
+        let mut thin = Self::with_capacity(len);
+
+        // SAFETY: the elements are moved into `thin`, and `thin` has space for all of them
+        unsafe {
+            vec.set_len(0);
+            let items = core::ptr::slice_from_raw_parts_mut(vec.as_mut_ptr(), len);
+            thin.init_tail(len, Init::from_raw(items));
+        }
+
+        thin
+    }
+}
+
+impl<T: MoveCtor> From<ThinVec<T>> for Vec<T> {
+    /// Move the elements out of the `ThinVec`
+    ///
+    /// If `T::IS_MOVE_TRIVIAL` holds and `T` is at least as aligned as a `usize`,
+    /// then the allocation is reused, otherwise the elements are moved with [`MoveCtor`]
+    fn from(mut thin: ThinVec<T>) -> Self {
+        let len = thin.len();
+        let capacity = thin.capacity();
+
+        if core::mem::size_of::<T>() == 0 {
+            let mut vec = Vec::new();
+            // SAFETY: zero-sized elements don't need to be copied, so ownership of them is moved
+            // by forgetting them in `thin` and adding them to the length of `vec`
+            unsafe {
+                if len != 0 {
+                    (*thin.as_header_mut_ptr()).len = 0;
+                }
+                vec.set_len(len);
+            }
+            return vec;
+        }
+
+        if capacity == 0 {
+            return Vec::new();
+        }
+
+        if can_reuse_alloc::<T>() {
+            let thin = ManuallyDrop::new(thin);
+            let new_layout = Layout::array::<T>(capacity).expect("Could not calculate new layout");
+            // SAFETY: the current layout was already calculated when allocating
+            let layout = unsafe {
+                init::layout_provider::layout_of::<AllocTy<T>, _>(&PushHeader(WithCapacity(
+                    capacity,
+                )))
+                .unwrap_unchecked()
+            };
+
+            // SAFETY: the thin vec was allocated by the global allocator with `layout`, which has the
+            // same alignment as `new_layout`, and the elements are trivially movable
+            unsafe {
+                let raw = thin.ptr.as_erased_mut_ptr().cast::<u8>();
+                raw.cast::<T>().copy_from(thin.as_ptr(), len);
+
+                let ptr = alloc::alloc::realloc(raw, layout, new_layout.size());
+                if ptr.is_null() {
+                    handle_alloc_error(new_layout);
+                }
+
+                return Vec::from_raw_parts(ptr.cast(), len, capacity);
+            }
+        }
+
+        let mut vec = Vec::with_capacity(len);
+        let mut drain = thin.drain(..);
+
+        // SAFETY: `vec` has space for all of the drained elements
+        unsafe {
+            let uninit =
+                init::Uninit::from_raw(core::ptr::slice_from_raw_parts_mut(vec.as_mut_ptr(), len));
+            uninit.init(drain.take_remaining()).take_ownership();
+            vec.set_len(len);
+        }
+
+        vec
+    }
+}
+
+pub(crate) struct WithCapacity(pub(crate) usize);
+
+pub(crate) struct WithCapacityLayoutProvider;
 
 unsafe impl<T> LayoutProvider<VecData<T>, WithCapacity> for WithCapacityLayoutProvider {
     fn layout_of(args: &WithCapacity) -> Option<core::alloc::Layout> {
@@ -392,3 +685,145 @@ fn test() {
 
     // panic!()
 }
+
+#[test]
+fn test_append_split_off() {
+    let mut a = ThinVec::<i32>::new();
+    let mut b = ThinVec::<i32>::new();
+
+    a.append(&mut b);
+    assert!(a.is_empty());
+
+    a.extend_from_slice(&[1, 2, 3]);
+    b.extend_from_slice(&[4, 5]);
+
+    a.append(&mut b);
+    assert_eq!(a.as_slice(), [1, 2, 3, 4, 5]);
+    assert!(b.is_empty());
+
+    let c = a.split_off(2);
+    assert_eq!(a.as_slice(), [1, 2]);
+    assert_eq!(c.as_slice(), [3, 4, 5]);
+
+    let d = a.split_off(2);
+    assert!(d.is_empty());
+    assert_eq!(a.as_slice(), [1, 2]);
+}
+
+#[test]
+fn test_splice() {
+    let mut v = ThinVec::<i32>::new();
+    v.extend_from_slice(&[1, 2, 3, 4, 5]);
+
+    let removed = v.splice(1..3, [20]).map(|x| x.into_inner()).sum::<i32>();
+    assert_eq!(removed, 5);
+    assert_eq!(v.as_slice(), [1, 20, 4, 5]);
+
+    v.splice(1..2, [10, 11, 12, 13]);
+    assert_eq!(v.as_slice(), [1, 10, 11, 12, 13, 4, 5]);
+
+    v.splice(..0, [-1, 0]);
+    assert_eq!(v.as_slice(), [-1, 0, 1, 10, 11, 12, 13, 4, 5]);
+
+    v.splice(2.., core::iter::empty::<i32>());
+    assert_eq!(v.as_slice(), [-1, 0]);
+
+    // more replacements than the iterator reports
+    v.splice(1..1, (2..6).filter(|_| true));
+    assert_eq!(v.as_slice(), [-1, 2, 3, 4, 5, 0]);
+    v.splice(1..5, core::iter::empty::<i32>());
+
+    let mut v = ThinVec::<i32>::new();
+    v.splice(.., [1, 2, 3]);
+    assert_eq!(v.as_slice(), [1, 2, 3]);
+}
+
+#[test]
+fn test_splice_panic() {
+    let mut v = ThinVec::<i32>::new();
+    v.extend_from_slice(&[1, 2, 3]);
+
+    // enough replacements to reallocate before the constructor panics
+    let replace_with = (0..64).map(|i| {
+        init::ctor(move |uninit: init::Uninit<'_, i32>| {
+            assert!(i != 63, "constructor panicked");
+            uninit.write(i)
+        })
+    });
+
+    let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+        v.splice(1..2, replace_with);
+    }));
+    assert!(result.is_err());
+    let expected = core::iter::once(1).chain(0..63).chain([3]);
+    assert!(v.as_slice().iter().copied().eq(expected));
+
+    // the replacements the iterator didn't report are dropped with the panic
+    let mut v = ThinVec::<i32>::new();
+    v.extend_from_slice(&[1, 2, 3]);
+
+    let replace_with = (0..64).filter(|_| true).map(|i| {
+        init::ctor(move |uninit: init::Uninit<'_, i32>| {
+            assert!(i != 63, "constructor panicked");
+            uninit.write(i)
+        })
+    });
+
+    let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+        v.splice(1..2, replace_with);
+    }));
+    assert!(result.is_err());
+    assert_eq!(v.as_slice(), [1, 0, 3]);
+}
+
+#[test]
+fn test_vec_conversions() {
+    use alloc::vec;
+
+    // `u64` can reuse the allocation on targets where it's as aligned as a `usize`
+    let v = vec![1u64, 2, 3];
+    let capacity = v.capacity();
+    let thin = ThinVec::from(v);
+    assert_eq!(thin.as_slice(), [1, 2, 3]);
+    assert_eq!(thin.capacity(), capacity);
+
+    let v = Vec::from(thin);
+    assert_eq!(v, [1, 2, 3]);
+    assert_eq!(v.capacity(), capacity);
+
+    let thin = ThinVec::from(vec![1u8, 2, 3]);
+    assert_eq!(thin.as_slice(), [1, 2, 3]);
+    assert_eq!(Vec::from(thin), [1, 2, 3]);
+
+    assert!(ThinVec::<u8>::from(Vec::new()).is_empty());
+    assert!(Vec::from(ThinVec::<u8>::new()).is_empty());
+}
+
+#[test]
+fn test_vec_conversions_zst() {
+    use alloc::vec;
+
+    let thin = ThinVec::from(vec![(); 3]);
+    assert_eq!(thin.len(), 3);
+    assert_eq!(Vec::from(thin), [(); 3]);
+
+    assert!(ThinVec::<()>::from(Vec::new()).is_empty());
+    assert!(Vec::from(ThinVec::<()>::new()).is_empty());
+}
+
+#[test]
+fn test_thin_ref() {
+    fn sum(thin: ThinRef<'_, [u16]>) -> u16 {
+        thin.iter().sum()
+    }
+
+    let mut v = ThinVec::<u16>::new();
+    assert_eq!(sum(v.as_thin_ref()), 0);
+
+    v.extend_from_slice(&[1, 2, 3]);
+    v.as_thin_mut()[0] = 10;
+    assert_eq!(sum(v.as_thin_ref()), 15);
+
+    let bx = ThinBox::<[u16]>::new(init::slice::ctor::CopyArgsLen(3, 4));
+    assert_eq!(sum(bx.as_thin_ref()), 12);
+}
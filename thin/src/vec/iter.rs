@@ -1,8 +1,8 @@
-use init::Init;
+use init::{ctor::MoveCtor, Ctor, Init};
 
 use crate::ptr::RawThinPtr;
 
-use super::VecData;
+use super::{ThinVec, VecData};
 
 pub struct Drain<'a, T> {
     pub(super) ptr: RawThinPtr<VecData<T>>,
@@ -11,6 +11,14 @@ pub struct Drain<'a, T> {
     pub(super) tail_offset: usize,
 }
 
+impl<'a, T> Drain<'a, T> {
+    /// Take all remaining elements out of the iterator
+    pub(super) fn take_remaining(&mut self) -> Init<'a, [T]> {
+        // SAFETY: the iterator is left empty, which is safe to drop
+        unsafe { self.iter.take_ownership() }.into_remaining()
+    }
+}
+
 impl<T> Drop for Drain<'_, T> {
     fn drop(&mut self) {
         unsafe {
@@ -40,12 +48,12 @@ impl<T> Drop for Drain<'_, T> {
             let tail_len = self.tail_len;
             let tail_start = data.add(self.tail_offset);
 
-            (*ptr).len += rem_len + tail_len;
-
             if rem_len == 0 && tail_len == 0 {
                 return;
             }
 
+            (*ptr).len += rem_len + tail_len;
+
             if tail_start == rem_end {
                 // one copy
                 dest.copy_from(rem_start, rem_len + tail_len);
@@ -57,7 +65,7 @@ impl<T> Drop for Drain<'_, T> {
             }
 
             if tail_len != 0 {
-                dest.copy_from(tail_start, tail_len)
+                dest.add(rem_len).copy_from(tail_start, tail_len)
             }
         }
     }
@@ -79,6 +87,140 @@ impl<'a, T> DoubleEndedIterator for Drain<'a, T> {
     }
 }
 
+pub struct Splice<'a, T: MoveCtor + Ctor<I::Item>, I: Iterator> {
+    pub(super) drain: Drain<'a, T>,
+    pub(super) replace_with: I,
+    pub(super) vec: *mut ThinVec<T>,
+}
+
+impl<T: MoveCtor + Ctor<I::Item>, I: Iterator> Splice<'_, T, I> {
+    /// Construct replacements in the gap between the vector's elements and the tail
+    ///
+    /// Returns false if `replace_with` ran out before the gap was filled
+    ///
+    /// # Safety
+    ///
+    /// The gap must be inside the vector's allocation
+    unsafe fn fill(&mut self) -> bool {
+        unsafe {
+            let ptr = self.drain.ptr.as_mut_ptr();
+            let data = core::ptr::addr_of_mut!((*ptr).data).cast::<T>();
+
+            while (*ptr).len < self.drain.tail_offset {
+                let Some(args) = self.replace_with.next() else {
+                    return false;
+                };
+
+                // if a constructor panics, the drain moves the tail back next to the elements
+                let uninit = init::Uninit::from_raw(data.add((*ptr).len));
+                uninit.init(args).take_ownership();
+                (*ptr).len += 1;
+            }
+
+            true
+        }
+    }
+
+    /// Make room for `additional` more replacements by moving the tail further along
+    ///
+    /// # Safety
+    ///
+    /// The gap between the vector's elements and the tail must be empty
+    unsafe fn move_tail(&mut self, additional: usize) {
+        unsafe {
+            let vec = &mut *self.vec;
+            let tail_len = core::mem::take(&mut self.drain.tail_len);
+
+            let ptr = self.drain.ptr.as_mut_ptr();
+            let len = (*ptr).len;
+
+            // the tail is part of the vector while it's reserving space, so that it's moved
+            // along if the vector is reallocated, or kept if reserving panics
+            if tail_len != 0 {
+                let data = core::ptr::addr_of_mut!((*ptr).data).cast::<T>();
+                data.add(len)
+                    .copy_from(data.add(self.drain.tail_offset), tail_len);
+                (*ptr).len = len + tail_len;
+            }
+
+            vec.reserve(additional);
+            self.drain.ptr = vec.ptr;
+
+            let ptr = self.drain.ptr.as_mut_ptr();
+            let data = core::ptr::addr_of_mut!((*ptr).data).cast::<T>();
+            if tail_len != 0 {
+                data.add(len + additional)
+                    .copy_from(data.add(len), tail_len);
+                (*ptr).len = len;
+            }
+
+            self.drain.tail_offset = len + additional;
+            self.drain.tail_len = tail_len;
+        }
+    }
+}
+
+impl<T: MoveCtor + Ctor<I::Item>, I: Iterator> Drop for Splice<'_, T, I> {
+    fn drop(&mut self) {
+        self.drain.by_ref().for_each(drop);
+
+        unsafe {
+            // fill the gap left by the drained elements, and if the replacements
+            // run out first, the drain moves the tail back into place
+            if !self.fill() {
+                return;
+            }
+
+            // there may be more replacements, so make room for as many as the iterator reports
+            let (lower, _) = self.replace_with.size_hint();
+            if lower != 0 {
+                self.move_tail(lower);
+                if !self.fill() {
+                    return;
+                }
+            }
+
+            // construct any replacements the iterator didn't report on the side,
+            // so that the tail only has to be moved once more
+            let mut rest = ThinVec::<T>::new();
+            for args in self.replace_with.by_ref() {
+                rest.emplace(args);
+            }
+
+            let rest_len = rest.len();
+            if rest_len == 0 {
+                return;
+            }
+
+            self.move_tail(rest_len);
+
+            let ptr = self.drain.ptr.as_mut_ptr();
+            let data = core::ptr::addr_of_mut!((*ptr).data).cast::<T>();
+            let gap = core::ptr::slice_from_raw_parts_mut(data.add((*ptr).len), rest_len);
+            init::Uninit::from_raw(gap)
+                .init(rest.drain(..).take_remaining())
+                .take_ownership();
+            (*ptr).len += rest_len;
+        }
+    }
+}
+
+impl<'a, T: MoveCtor + Ctor<I::Item>, I: Iterator> Iterator for Splice<'a, T, I> {
+    type Item = Init<'a, T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.drain.next()
+    }
+}
+
+impl<'a, T: MoveCtor + Ctor<I::Item>, I: Iterator> DoubleEndedIterator for Splice<'a, T, I> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.drain.next_back()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -110,6 +252,17 @@ mod tests {
         assert_eq!(tv.as_slice(), [10]);
     }
 
+    #[test]
+    pub fn test_drain_back_with_tail() {
+        let mut tv = crate::vec::ThinVec::<i32>::new();
+        tv.extend_from_slice(&[10, 20, 30, 40, 50]);
+
+        // the remaining elements and the tail aren't next to each other
+        tv.drain(1..4).next_back();
+
+        assert_eq!(tv.as_slice(), [10, 20, 30, 50]);
+    }
+
     #[test]
     pub fn test_drain_leak_amplification() {
         let mut tv = crate::vec::ThinVec::<i32>::new();